@@ -0,0 +1,152 @@
+use rocksdb::Error;
+
+// Backend-agnostic surface for the handful of operations 'Storage'/'StorageVersioned' actually need from
+// their underlying engine: opening a store, managing column families, and running an update inside a
+// transaction. 'Storage'/'StorageVersioned' themselves are not generic over this trait yet - they remain
+// concrete on top of 'rocksdb::TransactionDB' throughout 'InternalRef'/'InternalReader' (iterators,
+// snapshots, checkpoints and the rest of the 'Reader' surface are all expressed directly in terms of
+// rocksdb types). Retrofitting those call sites to go through 'StorageBackend' instead is a much larger,
+// separate change; this trait is the first step, scoped to the operations named in the request (open,
+// create_transaction, get_column_family, set_column_family, update, commit), and is meant to let a
+// lightweight non-RocksDB implementation (see 'MemoryBackend' below) stand in for unit tests that don't
+// need real persistence or the native RocksDB dependency, with an embedded on-disk alternative (e.g. LMDB)
+// addable as its own feature-gated module once this crate has a Cargo manifest to gate it with.
+pub trait StorageBackend: Sized {
+    type Transaction<'a> where Self: 'a;
+
+    // Opens (creating if requested and missing) the backend-specific store at 'path'
+    fn open(path: &str, create_if_missing: bool) -> Result<Self, Error>;
+
+    // Returns true if a column family with the given name already exists
+    fn get_column_family(&self, cf_name: &str) -> bool;
+
+    // Creates a column family with the given name; a no-op if it already exists
+    fn set_column_family(&mut self, cf_name: &str) -> Result<(), Error>;
+
+    // Reads the current value for 'key' in the 'default' column family, or None if absent
+    fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+
+    // Starts a new transaction against the current state of the store
+    fn create_transaction(&self) -> Result<Self::Transaction<'_>, Error>;
+
+    // Applies the given insertions/removals to the 'default' column family within 'transaction'
+    fn update(transaction: &Self::Transaction<'_>, to_update: &Vec<(&[u8], &[u8])>, to_delete: &Vec<&[u8]>) -> Result<(), Error>;
+
+    // Makes a transaction's changes durable and visible to subsequent reads
+    fn commit(transaction: Self::Transaction<'_>) -> Result<(), Error>;
+}
+
+// In-memory 'StorageBackend' over a plain 'BTreeMap', for tests and embedders that want the
+// versioned-commit logic without linking the native RocksDB dependency. Column families are modeled as
+// independent maps rather than a single keyspace, matching how 'Storage' keeps them isolated.
+pub mod memory {
+    use super::StorageBackend;
+    use rocksdb::Error;
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    pub struct MemoryBackend {
+        column_families: Mutex<BTreeMap<String, BTreeMap<Vec<u8>, Vec<u8>>>>,
+    }
+
+    // A transaction accumulates writes locally and only applies them to 'MemoryBackend' on 'commit',
+    // matching RocksDB's own transaction semantics (uncommitted writes aren't visible to other readers).
+    // The pending writes are kept behind a 'Mutex' (rather than requiring '&mut Self::Transaction') so
+    // 'update' can take '&self', matching the signature RocksDB's own transactions support.
+    pub struct MemoryTransaction<'a> {
+        backend: &'a MemoryBackend,
+        pending_update: Mutex<Vec<(Vec<u8>, Vec<u8>)>>,
+        pending_delete: Mutex<Vec<Vec<u8>>>,
+    }
+
+    impl StorageBackend for MemoryBackend {
+        type Transaction<'a> = MemoryTransaction<'a>;
+
+        fn open(_path: &str, _create_if_missing: bool) -> Result<Self, Error> {
+            Ok(MemoryBackend{ column_families: Mutex::new(BTreeMap::from([("default".to_string(), BTreeMap::new())])) })
+        }
+
+        fn get_column_family(&self, cf_name: &str) -> bool {
+            self.column_families.lock().unwrap().contains_key(cf_name)
+        }
+
+        fn set_column_family(&mut self, cf_name: &str) -> Result<(), Error> {
+            self.column_families.lock().unwrap().entry(cf_name.to_string()).or_insert_with(BTreeMap::new);
+            Ok(())
+        }
+
+        fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+            self.column_families.lock().unwrap().get("default")?.get(key).cloned()
+        }
+
+        fn create_transaction(&self) -> Result<Self::Transaction<'_>, Error> {
+            Ok(MemoryTransaction{ backend: self, pending_update: Mutex::new(Vec::new()), pending_delete: Mutex::new(Vec::new()) })
+        }
+
+        fn update(transaction: &Self::Transaction<'_>, to_update: &Vec<(&[u8], &[u8])>, to_delete: &Vec<&[u8]>) -> Result<(), Error> {
+            let mut pending_update = transaction.pending_update.lock().unwrap();
+            let mut pending_delete = transaction.pending_delete.lock().unwrap();
+            for &(key, value) in to_update {
+                pending_update.push((key.to_vec(), value.to_vec()));
+            }
+            for &key in to_delete {
+                pending_delete.push(key.to_vec());
+            }
+            Ok(())
+        }
+
+        fn commit(transaction: Self::Transaction<'_>) -> Result<(), Error> {
+            let mut column_families = transaction.backend.column_families.lock().unwrap();
+            let default_cf = column_families.entry("default".to_string()).or_insert_with(BTreeMap::new);
+            for (key, value) in transaction.pending_update.into_inner().unwrap() {
+                default_cf.insert(key, value);
+            }
+            for key in transaction.pending_delete.into_inner().unwrap() {
+                default_cf.remove(&key);
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::common::backend::StorageBackend;
+    use crate::common::backend::memory::MemoryBackend;
+
+    // Mirrors a representative subset of 'storage::test::storage_tests' (open, default-CF presence,
+    // set_column_family idempotency, uncommitted writes being invisible until commit) against
+    // 'MemoryBackend', to prove the 'StorageBackend' abstraction is actually usable by a second
+    // implementation rather than only compiling against 'Self = MemoryBackend' in isolation.
+    #[test]
+    fn memory_backend_tests(){
+        let mut backend = MemoryBackend::open("unused", true).unwrap();
+
+        // 'open' already creates the 'default' column family, matching 'Storage::open'
+        assert!(backend.get_column_family("default"));
+        assert!(!backend.get_column_family("other"));
+        backend.set_column_family("other").unwrap();
+        assert!(backend.get_column_family("other"));
+        // idempotent, like 'ColumnFamiliesManager::set_column_family'
+        backend.set_column_family("other").unwrap();
+
+        assert!(backend.get(b"k1").is_none());
+
+        let tx = backend.create_transaction().unwrap();
+        MemoryBackend::update(&tx, &vec![("k1".as_ref(), "v1".as_ref()), ("k2".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+
+        // uncommitted writes aren't visible yet
+        assert!(backend.get(b"k1").is_none());
+
+        MemoryBackend::commit(tx).unwrap();
+        assert_eq!(backend.get(b"k1").unwrap(), b"v1");
+        assert_eq!(backend.get(b"k2").unwrap(), b"v2");
+
+        let tx2 = backend.create_transaction().unwrap();
+        MemoryBackend::update(&tx2, &vec![], &vec!["k1".as_ref()]).unwrap();
+        MemoryBackend::commit(tx2).unwrap();
+        assert!(backend.get(b"k1").is_none());
+        assert_eq!(backend.get(b"k2").unwrap(), b"v2");
+    }
+}
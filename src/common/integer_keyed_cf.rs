@@ -0,0 +1,162 @@
+use rocksdb::{ColumnFamily, Direction, Error};
+use crate::common::{Reader, transaction::TransactionBasic};
+use std::collections::HashMap;
+
+// Encodes a 'u64' key as big-endian bytes so RocksDB's lexicographic key ordering matches the integers'
+// numeric ordering - native ('to_ne_bytes'/'to_le_bytes') encoding would not: e.g. 256u64 would sort
+// before 2u64 on a little-endian host, breaking range scans like 'iter_range' below.
+fn encode_key(key: u64) -> [u8; 8] {
+    key.to_be_bytes()
+}
+
+fn decode_key(bytes: &[u8]) -> u64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(&bytes[0..8]);
+    u64::from_be_bytes(buf)
+}
+
+// A typed view over a column family whose keys are 'u64's, so callers don't have to hand-encode numeric
+// keys as byte arrays themselves (and risk getting the byte order wrong, which would silently break
+// ordered scans). Every method here is a thin wrapper delegating to the byte-oriented 'Reader'/
+// 'TransactionBasic' methods 'reader' already implements - this adds a key codec, not a new storage
+// mechanism. Obtained via 'ColumnFamiliesManager::integer_keyed_cf'.
+pub struct IntegerKeyedCf<'a, T> {
+    reader: &'a T,
+    cf: &'a ColumnFamily,
+}
+
+impl<'a, T> IntegerKeyedCf<'a, T> {
+    pub fn new(reader: &'a T, cf: &'a ColumnFamily) -> Self {
+        IntegerKeyedCf{ reader, cf }
+    }
+}
+
+impl<'a, T: Reader> IntegerKeyedCf<'a, T> {
+    // Retrieves the value stored under 'key', or None if absent
+    pub fn get(&self, key: u64) -> Option<Vec<u8>> {
+        self.reader.get_cf(self.cf, &encode_key(key))
+    }
+
+    // Gets values for a specified list of keys; absent keys map to None. See 'Reader::multi_get_cf'.
+    pub fn multi_get(&self, keys: &[u64]) -> HashMap<u64, Option<Vec<u8>>> {
+        let encoded_keys: Vec<[u8; 8]> = keys.iter().map(|&key| encode_key(key)).collect();
+        let key_refs: Vec<&[u8]> = encoded_keys.iter().map(|key| key.as_ref()).collect();
+        self.reader.multi_get_cf(self.cf, &key_refs).into_iter()
+            .map(|(key, value)| (decode_key(&key), value))
+            .collect()
+    }
+
+    // Returns an iterator of every (key, value) pair in this column family, in ascending numeric key order
+    pub fn get_iter(&self) -> Result<Box<dyn Iterator<Item = (u64, Vec<u8>)> + 'a>, Error> {
+        let iter = self.reader.get_iter_cf(self.cf)?;
+        Ok(Box::new(iter.map(|(key, value)| (decode_key(&key), value.to_vec()))))
+    }
+
+    // Returns a lazy iterator of every (key, value) pair with a key between 'from' and 'to', pushing the
+    // bound down into RocksDB via 'Reader::get_range_iter_cf' rather than filtering client-side. The lower
+    // bound is always inclusive and the upper bound always exclusive, exactly like 'Reader::get_range_iter_cf'
+    // itself - only the traversal direction flips: 'from <= to' scans forward in ascending order over
+    // '[from, to)'; 'from > to' scans backward in descending order over the same-shaped '[to, from)' (so
+    // 'to' is included and 'from' is excluded) - so block-height-indexed data can be read in either
+    // direction without the caller having to reverse the result afterwards.
+    // Returns Err if this column family can't be iterated
+    pub fn iter_range(&self, from: u64, to: u64) -> Result<Box<dyn Iterator<Item = (u64, Vec<u8>)> + 'a>, Error> {
+        let (lower, upper, direction) = if from <= to {
+            (encode_key(from), encode_key(to), Direction::Forward)
+        } else {
+            (encode_key(to), encode_key(from), Direction::Reverse)
+        };
+        let iter = self.reader.get_range_iter_cf(self.cf, Some(&lower[..]), Some(&upper[..]), direction)?;
+        Ok(Box::new(iter.map(|(key, value)| (decode_key(&key), value.to_vec()))))
+    }
+}
+
+impl<'a, T: TransactionBasic> IntegerKeyedCf<'a, T> {
+    // Performs the specified insertions ('to_update' vector of key-values) and removals ('to_delete'
+    // vector of keys) in this typed column family, within the current transaction. See 'TransactionBasic::update_cf'.
+    // Returns Result with error message if any error occurred
+    pub fn update(&self, to_update: &Vec<(u64, &[u8])>, to_delete: &Vec<u64>) -> Result<(), Error> {
+        let encoded_update: Vec<([u8; 8], &[u8])> = to_update.iter().map(|&(key, value)| (encode_key(key), value)).collect();
+        let update_refs: Vec<(&[u8], &[u8])> = encoded_update.iter().map(|(key, value)| (key.as_ref(), *value)).collect();
+
+        let encoded_delete: Vec<[u8; 8]> = to_delete.iter().map(|&key| encode_key(key)).collect();
+        let delete_refs: Vec<&[u8]> = encoded_delete.iter().map(|key| key.as_ref()).collect();
+
+        self.reader.update_cf(self.cf, &update_refs, &delete_refs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::storage::Storage;
+    use crate::common::storage::ColumnFamiliesManager;
+    use crate::common::integer_keyed_cf::IntegerKeyedCf;
+    use crate::common::{test_dir, Reader};
+    use crate::common::transaction::TransactionBasic;
+
+    #[test]
+    fn integer_keyed_cf_tests(){
+        let (_tmp_dir, storage_path) = test_dir("integer_keyed_cf_tests").unwrap();
+        let mut storage = Storage::open(storage_path.as_str(), true).unwrap();
+        storage.set_column_family("heights").unwrap();
+        let cf = storage.get_column_family("heights").unwrap();
+
+        // the ColumnFamily handle always comes from Storage (the ColumnFamiliesManager); a Transaction
+        // isn't one itself, so its typed view is built directly via 'IntegerKeyedCf::new' rather than
+        // through 'ColumnFamiliesManager::integer_keyed_cf' (Storage-only convenience, used below)
+        let tx = storage.create_transaction().unwrap();
+        let typed_tx = IntegerKeyedCf::new(&tx, cf);
+        typed_tx.update(
+            &vec![(1u64, "block1".as_ref()), (2u64, "block2".as_ref()), (256u64, "block256".as_ref()), (257u64, "block257".as_ref())],
+            &vec![]
+        ).unwrap();
+        tx.commit().unwrap();
+
+        let typed = storage.integer_keyed_cf("heights").unwrap();
+        assert_eq!(typed.get(1).unwrap(), b"block1");
+        assert_eq!(typed.get(256).unwrap(), b"block256");
+        assert!(typed.get(3).is_none());
+
+        let values = typed.multi_get(&[1, 2, 3, 256]);
+        assert_eq!(values[&1].as_ref().unwrap(), &b"block1".to_vec());
+        assert_eq!(values[&2].as_ref().unwrap(), &b"block2".to_vec());
+        assert!(values[&3].is_none());
+        assert_eq!(values[&256].as_ref().unwrap(), &b"block256".to_vec());
+
+        // big-endian key encoding keeps ascending numeric order even across a byte-length boundary (2 < 256)
+        let all: Vec<(u64, Vec<u8>)> = typed.get_iter().unwrap().collect();
+        assert_eq!(all, vec![
+            (1, b"block1".to_vec()),
+            (2, b"block2".to_vec()),
+            (256, b"block256".to_vec()),
+            (257, b"block257".to_vec()),
+        ]);
+
+        // forward range: '[2, 257)'
+        let forward: Vec<(u64, Vec<u8>)> = typed.iter_range(2, 257).unwrap().collect();
+        assert_eq!(forward, vec![(2, b"block2".to_vec()), (256, b"block256".to_vec())]);
+
+        // backward range (from > to): keys in '[2, 300)' visited in descending order
+        let backward: Vec<(u64, Vec<u8>)> = typed.iter_range(300, 2).unwrap().collect();
+        assert_eq!(backward, vec![
+            (257, b"block257".to_vec()),
+            (256, b"block256".to_vec()),
+            (2, b"block2".to_vec()),
+        ]);
+
+        // backward range where 'from' itself is a stored key: '[to, from)' excludes 'from' and includes
+        // 'to', the same inclusive-lower/exclusive-upper shape the forward case has
+        let backward_boundary: Vec<(u64, Vec<u8>)> = typed.iter_range(257, 2).unwrap().collect();
+        assert_eq!(backward_boundary, vec![
+            (256, b"block256".to_vec()),
+            (2, b"block2".to_vec()),
+        ]);
+
+        // deleting through the typed view removes the same underlying key a byte-level update_cf would
+        let tx2 = storage.create_transaction().unwrap();
+        let typed_tx2 = IntegerKeyedCf::new(&tx2, cf);
+        typed_tx2.update(&vec![], &vec![1u64]).unwrap();
+        tx2.commit().unwrap();
+        assert!(storage.integer_keyed_cf("heights").unwrap().get(1).is_none());
+    }
+}
@@ -25,6 +25,26 @@ pub(crate) fn set_column_family(
     }
 }
 
+pub(crate) fn delete_column_family(
+    cf_manager: &mut dyn ColumnFamiliesManager,
+    _env: JNIEnv,
+    _cf_name: JString
+){
+    let cf_name = _env
+        .get_string(_cf_name)
+        .expect("Should be able to read _cf_name jstring as JavaStr");
+
+    match cf_manager.delete_column_family(cf_name.to_str().expect("Should be able to convert the cf_name to Rust String")) {
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot delete column family: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
 pub(crate) fn get_column_family(
     cf_manager: &dyn ColumnFamiliesManager,
     _env: JNIEnv,
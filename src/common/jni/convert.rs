@@ -0,0 +1,231 @@
+use jni::JNIEnv;
+use jni::errors::Error;
+use jni::objects::{JObject, JString};
+use jni::sys::{jboolean, jbyteArray, jint, jobject, jobjectArray, jstring};
+use crate::common::jni::create_jarray;
+
+// Converts a Java-side argument type into its Rust counterpart. Every wrapper in this module currently
+// reads its arguments by hand ('_env.get_string(...).expect(...)', '_env.convert_byte_array(...).expect(...)',
+// a manual 'get_array_length'/'get_object_array_element' loop for 'jobjectArray', ...) - 'FromJava' names
+// that conversion once per Rust type instead of once per call site, so a new native wrapper can just ask
+// for the Rust type it wants. Unlike the call sites it replaces (which '.expect()' and let a conversion
+// failure unwind as a panic across the FFI boundary), 'from_java' surfaces a 'jni::errors::Error' so a
+// caller can route it through the exception subsystem instead.
+pub(crate) trait FromJava<'env>: Sized {
+    type From;
+    fn from_java(env: &JNIEnv<'env>, value: Self::From) -> Result<Self, Error>;
+}
+
+// Converts a Rust value produced by a native method back into its Java-side representation.
+pub(crate) trait IntoJava<'env> {
+    type Into;
+    fn into_java(self, env: &JNIEnv<'env>) -> Result<Self::Into, Error>;
+}
+
+// Names the Java class an 'IntoJava' element type converts to, so a container conversion (e.g. the
+// blanket 'Vec<E>' impl below) can look up the element class to build its Java array from, instead of
+// hard-coding a class name literal per container as 'map_to_java_list_of_values'/'create_jarray' call
+// sites currently do.
+pub(crate) trait JavaClassName {
+    fn class_name() -> &'static str;
+}
+
+impl<'env> FromJava<'env> for String {
+    type From = JString<'env>;
+    fn from_java(env: &JNIEnv<'env>, value: JString<'env>) -> Result<Self, Error> {
+        Ok(env.get_string(value)?.into())
+    }
+}
+
+impl<'env> FromJava<'env> for Vec<u8> {
+    type From = jbyteArray;
+    fn from_java(env: &JNIEnv<'env>, value: jbyteArray) -> Result<Self, Error> {
+        env.convert_byte_array(value)
+    }
+}
+
+impl<'env> FromJava<'env> for Vec<Vec<u8>> {
+    type From = jobjectArray;
+    fn from_java(env: &JNIEnv<'env>, value: jobjectArray) -> Result<Self, Error> {
+        let len = env.get_array_length(value)?;
+        (0..len).map(|i| {
+            let element = env.get_object_array_element(value, i)?;
+            env.convert_byte_array(element.into_inner())
+        }).collect()
+    }
+}
+
+// Trivial (infallible) conversions for the Java primitive types a constructor-shaped wrapper commonly
+// takes alongside a path/byte-array argument (e.g. 'create_if_missing', a stored-versions count) - unlike
+// the object conversions above, these can't actually fail, but still need a 'FromJava' impl so
+// 'jni_export!'/'jni_export_ctor!' can convert every argument uniformly through the same trait
+impl<'env> FromJava<'env> for bool {
+    type From = jboolean;
+    fn from_java(_env: &JNIEnv<'env>, value: jboolean) -> Result<Self, Error> {
+        Ok(value != 0)
+    }
+}
+
+impl<'env> FromJava<'env> for i32 {
+    type From = jint;
+    fn from_java(_env: &JNIEnv<'env>, value: jint) -> Result<Self, Error> {
+        Ok(value)
+    }
+}
+
+impl JavaClassName for String {
+    fn class_name() -> &'static str { "java/lang/String" }
+}
+
+impl<'env> IntoJava<'env> for String {
+    type Into = jstring;
+    fn into_java(self, env: &JNIEnv<'env>) -> Result<jstring, Error> {
+        Ok(env.new_string(self)?.into_inner())
+    }
+}
+
+impl<'env> IntoJava<'env> for Vec<u8> {
+    type Into = jbyteArray;
+    fn into_java(self, env: &JNIEnv<'env>) -> Result<jbyteArray, Error> {
+        env.byte_array_from_slice(self.as_slice())
+    }
+}
+
+// A missing value converts to a null 'jbyteArray' rather than an empty one, matching the 'Option'-as-null
+// convention already used for e.g. 'Reader::get_cf's Java-side wrappers.
+impl<'env> IntoJava<'env> for Option<Vec<u8>> {
+    type Into = jbyteArray;
+    fn into_java(self, env: &JNIEnv<'env>) -> Result<jbyteArray, Error> {
+        match self {
+            Some(value) => value.into_java(env),
+            None => Ok(JObject::null().into_inner()),
+        }
+    }
+}
+
+// Converts a Rust key/value pair into a Java 'AbstractMap.SimpleEntry', mirroring 'create_jentry'
+impl<'env> IntoJava<'env> for (Vec<u8>, Vec<u8>) {
+    type Into = jobject;
+    fn into_java(self, env: &JNIEnv<'env>) -> Result<jobject, Error> {
+        let (key, value) = self;
+        let jkey = key.into_java(env)?;
+        let jvalue = value.into_java(env)?;
+
+        let entry_class = env.find_class("java/util/AbstractMap$SimpleEntry")?;
+        Ok(
+            env.new_object(entry_class, "(Ljava/lang/Object;Ljava/lang/Object;)V", &[jkey.into(), jvalue.into()])?
+                .into_inner()
+        )
+    }
+}
+
+// Builds a Java array of 'E::class_name()' by converting each element through 'IntoJava', replacing the
+// one-off 'impl IntoJava for Vec<String>' this used to be: any element type that names its own Java class
+// gets array support for free. 'E::Into' is constrained to 'jobject' (rather than e.g. 'jbyteArray' or
+// 'jstring', which are the same underlying pointer type but distinct aliases) so 'create_jarray' can treat
+// every element uniformly; this holds for 'String' ('jstring = jobject') and would hold for any future
+// object-typed 'E' the same way.
+impl<'env, E: IntoJava<'env, Into = jobject> + JavaClassName> IntoJava<'env> for Vec<E> {
+    type Into = jobjectArray;
+    fn into_java(self, env: &JNIEnv<'env>) -> Result<jobjectArray, Error> {
+        let element_class = env.find_class(E::class_name())?;
+        let objects = self.into_iter()
+            .map(|element| element.into_java(env))
+            .collect::<Result<Vec<jobject>, Error>>()?;
+        Ok(create_jarray(env, element_class, std::ptr::null_mut(), objects))
+    }
+}
+
+// Declarative stand-in for the attribute proc-macro requested to auto-generate the '#[no_mangle] extern
+// "system"' native method shim: this crate has no Cargo/workspace manifest in which a genuine proc-macro
+// crate (which needs its own 'proc-macro = true' crate root, separate from this one) could be declared, so
+// a 'macro_rules!' is used here instead to get the same one-annotation ergonomics without new crate
+// infrastructure. It covers the common shape seen throughout 'storage/jni.rs'/'storage_versioned/jni.rs':
+// unwrap the receiver pointer via 'try_unwrap_ptr' (throwing 'IllegalStateException' on a null/closed
+// handle instead of panicking), convert each argument through 'FromJava', run a fallible body, and on 'Ok'
+// convert the value through 'IntoJava' - or on any 'Err' along the way, throw the error's own Java
+// exception via 'JniThrowable'/'throw_result' rather than panicking across the FFI boundary.
+// Used so far only by 'nativeListVersions' in 'storage_versioned/jni.rs' - retrofitting the hundreds of
+// current one-off wrappers onto this is a separate, much larger change; this is the first step, scoped to
+// making *new* wrappers one line longer instead of ~15. 'get_field_name' is already the single source of truth for the pointer-field mapping,
+// since 'try_unwrap_ptr' (used below) calls it rather than this macro duplicating that lookup itself.
+macro_rules! jni_export {
+    (
+        fn $name:ident($self_binding:ident : &$self_ty:ty $(, $arg:ident : $arg_ty:ty)*) -> $ret_ty:ty
+        $body:block
+    ) => {
+        #[no_mangle]
+        pub extern "system" fn $name<'env>(
+            _env: jni::JNIEnv<'env>,
+            _this: jni::objects::JObject<'env>,
+            $($arg: <$arg_ty as $crate::common::jni::convert::FromJava<'env>>::From),*
+        ) -> <$ret_ty as $crate::common::jni::convert::IntoJava<'env>>::Into {
+            let $self_binding: &$self_ty = match $crate::common::jni::try_unwrap_ptr(&_env, _this) {
+                Ok(value) => value,
+                Err(e) => throw!(&_env, $crate::common::jni::exception::JniThrowable::jclass(&e), $crate::common::jni::exception::JniThrowable::message(&e).as_str(), Default::default())
+            };
+            $(
+                let $arg: $arg_ty = match $crate::common::jni::convert::FromJava::from_java(&_env, $arg) {
+                    Ok(value) => value,
+                    Err(e) => throw!(&_env, $crate::common::jni::exception::JniThrowable::jclass(&e), $crate::common::jni::exception::JniThrowable::message(&e).as_str(), Default::default())
+                };
+            )*
+            let result: Result<$ret_ty, rocksdb::Error> = (|| { $body })();
+            let value = $crate::common::jni::exception::throw_result(&_env, result);
+            match $crate::common::jni::convert::IntoJava::into_java(value, &_env) {
+                Ok(converted) => converted,
+                Err(e) => throw!(&_env, $crate::common::jni::exception::JniThrowable::jclass(&e), $crate::common::jni::exception::JniThrowable::message(&e).as_str(), Default::default())
+            }
+        }
+    };
+}
+
+// Constructor-shaped counterpart of 'jni_export!', for the other recurring wrapper shape - 'nativeOpen'
+// and friends - that 'jni_export!' above does not cover: there is no receiver pointer to unwrap (the
+// first parameter is a static 'JClass', not an already-boxed 'JObject'), and a successful result is
+// *boxed into a brand-new Java object* rather than converted in place through 'IntoJava'. Cramming both
+// shapes into a single macro arm was considered and rejected: the bodies share almost nothing (no
+// self-binding, no 'IntoJava' on the success path) and forcing one macro to branch on that would make the
+// already-unverifiable (no compiler in this environment) expansion harder to read, not easier.
+// Boxing itself defaults to the plain single-pointer 'create_java_object', but a type whose Java
+// constructor also needs a companion field (e.g. 'OptimisticStorageVersioned's default-CF pointer, boxed
+// by 'create_storage_java_object') can name that boxing function explicitly via the 'via $create_fn' form.
+macro_rules! jni_export_ctor {
+    (
+        fn $name:ident($($arg:ident : $arg_ty:ty),*) -> $ret_ty:ty as $class:expr
+        $body:block
+    ) => {
+        jni_export_ctor!(
+            fn $name($($arg : $arg_ty),*) -> $ret_ty as $class, via $crate::common::jni::create_java_object
+            $body
+        );
+    };
+
+    (
+        fn $name:ident($($arg:ident : $arg_ty:ty),*) -> $ret_ty:ty as $class:expr, via $create_fn:path
+        $body:block
+    ) => {
+        #[no_mangle]
+        pub extern "system" fn $name<'env>(
+            _env: jni::JNIEnv<'env>,
+            _class: jni::objects::JClass<'env>,
+            $($arg: <$arg_ty as $crate::common::jni::convert::FromJava<'env>>::From),*
+        ) -> jni::sys::jobject {
+            $(
+                let $arg: $arg_ty = match $crate::common::jni::convert::FromJava::from_java(&_env, $arg) {
+                    Ok(value) => value,
+                    Err(e) => throw!(&_env, $crate::common::jni::exception::JniThrowable::jclass(&e), $crate::common::jni::exception::JniThrowable::message(&e).as_str(), jni::objects::JObject::null().into_inner())
+                };
+            )*
+            let result: Result<$ret_ty, rocksdb::Error> = (|| { $body })();
+            match result {
+                Ok(value) => {
+                    let class = _env.find_class($class)
+                        .expect(&("Should be able to find class ".to_owned() + $class));
+                    $create_fn(&_env, &class, value)
+                }
+                Err(e) => throw!(&_env, $crate::common::jni::exception::JniThrowable::jclass(&e), $crate::common::jni::exception::JniThrowable::message(&e).as_str(), jni::objects::JObject::null().into_inner())
+            }
+        }
+    };
+}
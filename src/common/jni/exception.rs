@@ -1,5 +1,28 @@
 use jni::JNIEnv;
 
+// Names the Java exception a Rust error should surface as once it reaches a JNI entry point, so a
+// fallible call can be routed through 'throw_result' instead of each call site hand-rolling its own
+// 'match ... { Err(e) => throw!(...) }' with a hard-coded class name and 'format!("{:?}", e)' message.
+pub(crate) trait JniThrowable {
+    fn jclass(&self) -> &str;
+    fn message(&self) -> String;
+}
+
+// Both of the error types a native method body can currently fail with - a storage operation
+// ('rocksdb::Error') or a Java-side argument/return conversion ('jni::errors::Error') - are thrown as a
+// plain 'java/lang/Exception', matching the class name every hand-written wrapper already throws; only
+// the handle-lookup errors ('NullHandleError' in 'jni/mod.rs', 'StaleHandleError' in
+// 'jni/handle_registry.rs') are specific enough to warrant a more precise JDK exception class.
+impl JniThrowable for rocksdb::Error {
+    fn jclass(&self) -> &str { "java/lang/Exception" }
+    fn message(&self) -> String { format!("{:?}", self) }
+}
+
+impl JniThrowable for jni::errors::Error {
+    fn jclass(&self) -> &str { "java/lang/Exception" }
+    fn message(&self) -> String { format!("{:?}", self) }
+}
+
 pub(crate) fn _throw_inner(env: &JNIEnv, exception: &str, description: &str) {
     // Do nothing if there is a pending Java-exception that will be thrown
     // automatically by the JVM when the native method returns.
@@ -26,3 +49,18 @@ macro_rules! throw {
         return;
     }};
 }
+
+// Catches a 'Result' at a JNI entry point: on 'Err', throws 'e's Java exception via '_throw_inner' and
+// yields 'T::default()' so the caller can still return a (unused by the JVM, since an exception is now
+// pending) value of the expected native-method return type, instead of letting the failure '.expect()'
+// its way into a panic that unwinds across the FFI boundary - which is undefined behavior and aborts the
+// JVM without giving Java code anything it could catch.
+pub(crate) fn throw_result<T: Default>(env: &JNIEnv, result: Result<T, impl JniThrowable>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(e) => {
+            _throw_inner(env, e.jclass(), e.message().as_str());
+            T::default()
+        }
+    }
+}
@@ -24,6 +24,14 @@ pub(crate) fn parse_starting_key(env: &JNIEnv, mode: i32, jstarting_key: jbyteAr
     }
 }
 
+pub(crate) fn parse_direction(direction: i32) -> Result<Direction, Error> {
+    match direction {
+        ITER_DIRECTION_FORWARD => { Ok(Direction::Forward) }
+        ITER_DIRECTION_REVERSE => { Ok(Direction::Reverse) }
+        _ => { Err(Error::new(format!("Invalid iterator's direction: {:?}", direction).into())) }
+    }
+}
+
 pub(crate) fn parse_iterator_mode(mode: i32,
                                   starting_key: &[u8],
                                   direction: i32) -> Result<IteratorMode, Error> {
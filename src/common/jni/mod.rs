@@ -2,15 +2,17 @@ use jni::JNIEnv;
 use jni::objects::{JObject, JClass, JValue, JList};
 use std::any::TypeId;
 use crate::storage::Storage;
-use rocksdb::{ColumnFamily, DBIterator};
+use rocksdb::{ColumnFamily, DBIterator, Snapshot};
 use crate::storage::transaction::Transaction;
+use crate::storage::optimistic::OptimisticStorage;
+use crate::storage::optimistic_transaction::OptimisticTransaction;
 use std::collections::HashMap;
 use jni::sys::{jobject, jlong, jobjectArray};
-use jni::signature::JavaType;
-use jni::signature::Primitive::Boolean;
 use crate::common::storage::{ColumnFamiliesManager, DEFAULT_CF_NAME};
 use crate::storage_versioned::StorageVersioned;
 use crate::storage_versioned::transaction_versioned::TransactionVersioned;
+use crate::storage_versioned::optimistic::OptimisticStorageVersioned;
+use crate::storage_versioned::optimistic_transaction::OptimisticTransactionVersioned;
 
 #[macro_use]
 pub mod exception;
@@ -18,6 +20,10 @@ pub mod iterator;
 pub mod reader;
 pub mod transaction_basic;
 pub mod cf_manager;
+pub mod integer_keyed_cf;
+#[macro_use]
+pub(crate) mod convert;
+pub(crate) mod handle_registry;
 
 fn read_raw_pointer<'a, T>(input: *const T) -> &'a T {
     assert!(!input.is_null());
@@ -36,18 +42,33 @@ fn get_field_name<'a, T: 'static>() -> &'a str {
     else if TypeId::of::<T>() == TypeId::of::<Transaction>(){
         "transactionPointer"
     }
+    else if TypeId::of::<T>() == TypeId::of::<OptimisticStorage>(){
+        "optimisticStoragePointer"
+    }
+    else if TypeId::of::<T>() == TypeId::of::<OptimisticTransaction>(){
+        "optimisticTransactionPointer"
+    }
     else if TypeId::of::<T>() == TypeId::of::<StorageVersioned>(){
         "storageVersionedPointer"
     }
     else if TypeId::of::<T>() == TypeId::of::<TransactionVersioned>(){
         "transactionVersionedPointer"
     }
+    else if TypeId::of::<T>() == TypeId::of::<OptimisticStorageVersioned>(){
+        "optimisticStorageVersionedPointer"
+    }
+    else if TypeId::of::<T>() == TypeId::of::<OptimisticTransactionVersioned>(){
+        "optimisticTransactionVersionedPointer"
+    }
     else if TypeId::of::<T>() == TypeId::of::<ColumnFamily>(){
         "columnFamilyPointer"
     }
     else if TypeId::of::<T>() == TypeId::of::<DBIterator>(){
         "dbIteratorPointer"
     }
+    else if TypeId::of::<T>() == TypeId::of::<Snapshot<'static>>(){
+        "snapshotPointer"
+    }
     else {
         panic!("Unknown type of a pointer")
     }
@@ -68,6 +89,78 @@ pub fn unwrap_mut_ptr<'a, T: 'static>(env: &JNIEnv, ptr: JObject) -> &'a mut T {
     read_mut_raw_pointer(get_raw_ptr(env, ptr))
 }
 
+// A closed/never-opened handle: the Java-side object still exists, but its backing Rust pointer is null
+// (the field was never set, or 'close'/'nativeClose' already zeroed it out). Surfaces as a catchable
+// 'IllegalStateException' through 'try_unwrap_ptr'/'try_unwrap_mut_ptr' instead of an '!input.is_null()'
+// assertion panicking across the FFI boundary.
+pub(crate) struct NullHandleError {
+    field_name: &'static str,
+}
+
+impl crate::common::jni::exception::JniThrowable for NullHandleError {
+    fn jclass(&self) -> &str { "java/lang/IllegalStateException" }
+    fn message(&self) -> String {
+        format!("Native handle '{}' is null - object already closed?", self.field_name)
+    }
+}
+
+// Fallible counterparts of 'get_raw_ptr'/'unwrap_ptr'/'unwrap_mut_ptr' for new call sites (currently just
+// the 'jni_export!' macro) that want a catchable exception instead of a panic on a null handle. The
+// panicking originals above are left in place: retrofitting every existing hand-written wrapper in
+// 'storage/jni.rs'/'storage_versioned/jni.rs' onto the fallible path is a much larger, separate change.
+pub(crate) fn try_get_raw_ptr<T: 'static>(env: &JNIEnv, ptr: JObject) -> Result<*mut T, NullHandleError> {
+    let field_name = get_field_name::<T>();
+    let raw_ptr = get_raw_ptr::<T>(env, ptr);
+    if raw_ptr.is_null() {
+        Err(NullHandleError { field_name })
+    } else {
+        Ok(raw_ptr)
+    }
+}
+
+pub(crate) fn try_unwrap_ptr<'a, T: 'static>(env: &JNIEnv, ptr: JObject) -> Result<&'a T, NullHandleError> {
+    try_get_raw_ptr(env, ptr).map(|raw_ptr| unsafe { &*raw_ptr })
+}
+
+pub(crate) fn try_unwrap_mut_ptr<'a, T: 'static>(env: &JNIEnv, ptr: JObject) -> Result<&'a mut T, NullHandleError> {
+    try_get_raw_ptr(env, ptr).map(|raw_ptr| unsafe { &mut *raw_ptr })
+}
+
+// Field name every 'handle_registry'-backed Java wrapper class stores its opaque 'u64' handle under.
+// Unlike 'get_field_name' above, this name is shared across every 'T' - 'handle_registry::lookup' is what
+// tells two handles of different 'T' apart (by 'TypeId'), not the field name, so there's no need for a
+// per-type name here the way the raw-pointer scheme needs one per 'T' to satisfy 'env.get_field's type tag.
+const HANDLE_FIELD_NAME: &str = "handle";
+
+// Handle-registry-backed counterpart of 'create_java_object': boxes 'rust_object' behind an opaque
+// 'handle_registry' handle instead of exposing its raw address to Java. See 'handle_registry' for why, and
+// 'storage_versioned::jni's 'VersionSnapshot'/'CurrentStateSnapshot' wrappers for the first real call site.
+pub(crate) fn create_java_object_via_handle<T: 'static>(env: &JNIEnv, class: &JClass, rust_object: T) -> jobject {
+    let handle = handle_registry::register(rust_object);
+    env.new_object(*class, "(J)V", &[JValue::Long(handle as jlong)])
+        .expect("Should be able to create new Java-object")
+        .into_inner()
+}
+
+// Handle-registry-backed counterpart of 'unwrap_ptr': looks 'ptr's handle field up through
+// 'handle_registry::lookup', yielding a catchable 'StaleHandleError' instead of dereferencing a stale or
+// wrongly-typed handle the way 'unwrap_ptr' would dereference a stale or wrongly-typed raw pointer.
+pub(crate) fn unwrap_via_handle<'a, T: 'static>(env: &JNIEnv, ptr: JObject) -> Result<&'a T, handle_registry::StaleHandleError> {
+    let handle = env.get_field(ptr, HANDLE_FIELD_NAME, "J")
+        .expect("Should be able to get field handle")
+        .j().unwrap() as u64;
+    handle_registry::lookup::<T>(handle).map(|raw| unsafe { &*raw })
+}
+
+// Handle-registry-backed counterpart of the raw-pointer 'nativeClose' pattern
+// ('if !ptr.is_null() { drop(Box::from_raw(ptr)) }'): removes 'handle' from the registry and drops its
+// boxed 'T'. Takes the handle directly as a 'jlong', like every existing 'nativeClose' takes its raw
+// pointer directly, rather than a 'JObject' - by the time 'nativeClose' runs, the Java side has already
+// read the field once to pass it in, so there's no need to look it up again here.
+pub(crate) fn free_via_handle<T: 'static>(handle: jlong) {
+    let _ = handle_registry::free::<T>(handle as u64);
+}
+
 // Creates a wrapping Java-object (this is an object containing a pointer to Rust-object,
 // which is passed as a single parameter to the Java-object's constructor)
 pub fn create_java_object<T>(env: &JNIEnv, class: &JClass, rust_object: T) -> jobject {
@@ -204,39 +297,50 @@ pub fn create_jentry(_env: &JNIEnv, key: &[u8], value: &[u8]) -> jobject {
     jentry.into_inner()
 }
 
-// Converts HashMap<Vec<u8>, Option<Vec<u8>>> to Java List<byte[]>> of values in the same order as the 'keys' are given
-pub fn map_to_java_list_of_values(_env: &JNIEnv, keys: &Vec<Vec<u8>>, keys_map: &HashMap<Vec<u8>, Option<Vec<u8>>>) -> jobject {
-    let array_list_class = _env
-        .find_class("java/util/ArrayList")
-        .expect("Should be able to find ArrayList class");
-
-    let jlist = _env
-        .new_object(array_list_class, "()V", &[])
-        .expect("Should be able to create ArrayList object");
-
-    let add = _env.get_method_id(
-        array_list_class,
-        "add",
-        "(Ljava/lang/Object;)Z",
-    ).expect("Should be able to get the 'add' method ID of ArrayList object");
-
-    keys.iter().for_each(|key|{
-        let jvalue =
-            if let Some(value) = keys_map.get(key).expect("Key should exist in a given keys_map"){
-                _env.byte_array_from_slice(value.as_slice())
-                    .expect("Cannot convert Value to jbyteArray")
-            } else {
-                JObject::null().into_inner()
-            };
-        _env.call_method_unchecked(
-            jlist,
-            add,
-            JavaType::Primitive(Boolean),
-            vec![JValue::from(jvalue)].as_slice()
-        ).expect("Should be able to call the 'add' method of ArrayList object");
-    });
-
-    jlist.into_inner()
+// Builds a Java array of 'element_class' by mapping each of 'elements' through 'map', one at a time,
+// instead of forcing the caller to pre-build a whole 'Vec<jobject>' up front the way 'create_jarray'
+// does - this lets a caller stream keys/values/'SimpleEntry' pairs straight out of an iterator without
+// ever materializing an intermediate 'Vec<jobject>'. 'default' lazily supplies the array's fill object
+// (every slot below is immediately overwritten by 'map', so it's only ever used internally by
+// 'new_object_array' before that happens).
+pub fn rust_vec_to_java<T, F, G>(env: &JNIEnv, elements: Vec<T>, element_class: &str, map: F, default: G) -> jobjectArray
+where
+    F: Fn(&JNIEnv, T) -> jobject,
+    G: Fn() -> jobject,
+{
+    let obj_class = env.find_class(element_class)
+        .expect(&("Should be able to find class ".to_owned() + element_class));
+
+    let java_array = env
+        .new_object_array(elements.len() as i32, obj_class, default())
+        .expect("Should be able to create array of jobjects");
+
+    for (i, element) in elements.into_iter().enumerate() {
+        let jobj = map(env, element);
+        env.set_object_array_element(java_array, i as i32, jobj)
+            .expect("Should be able to add object to java array");
+    }
+    java_array
+}
+
+// Converts HashMap<Vec<u8>, Option<Vec<u8>>> to a Java byte[][] of values in the same order as the 'keys'
+// are given, mapping a missing value to a null array slot rather than an empty one
+pub fn map_to_java_list_of_values(_env: &JNIEnv, keys: &Vec<Vec<u8>>, keys_map: &HashMap<Vec<u8>, Option<Vec<u8>>>) -> jobjectArray {
+    let values: Vec<Option<Vec<u8>>> = keys.iter()
+        .map(|key| keys_map.get(key).expect("Key should exist in a given keys_map").clone())
+        .collect();
+
+    rust_vec_to_java(
+        _env,
+        values,
+        "[B",
+        |env, value| match value {
+            Some(bytes) => env.byte_array_from_slice(bytes.as_slice())
+                .expect("Cannot convert Value to jbyteArray"),
+            None => JObject::null().into_inner(),
+        },
+        || JObject::null().into_inner(),
+    )
 }
 
 // Converts List<byte[]> to Vec<Vec<u8>>
@@ -2,9 +2,124 @@ use crate::common::Reader;
 use jni::objects::JObject;
 use jni::JNIEnv;
 use jni::sys::{jbyteArray, jobjectArray, jobject, jboolean, JNI_TRUE, jint};
-use rocksdb::ColumnFamily;
+use rocksdb::{ColumnFamily, Snapshot};
 use crate::common::jni::{unwrap_ptr, java_array_to_vec_byte, map_to_java_map, create_java_object, exception::_throw_inner};
-use crate::common::jni::iterator::{parse_starting_key, parse_iterator_mode};
+use crate::common::jni::iterator::{parse_starting_key, parse_iterator_mode, parse_direction};
+
+// A null bound array leaves that side of the range unbounded, matching 'Reader::get_range_iter_cf's
+// 'Option<&[u8]>' parameters
+fn parse_bound(_env: &JNIEnv, _bound: jbyteArray) -> Option<Vec<u8>> {
+    if _bound.is_null() {
+        None
+    } else {
+        Some(_env.convert_byte_array(_bound).expect("Should be able to convert a bound to Rust byte array"))
+    }
+}
+
+// A null '_snapshot' JObject means "read the live DB state"; otherwise it points to a Snapshot
+// previously obtained via 'nativeCreateSnapshot' and reads/iterates a fixed point-in-time view
+fn resolve_snapshot<'a>(_env: &JNIEnv, _snapshot: JObject<'a>) -> Option<&'a Snapshot<'a>> {
+    if _snapshot.is_null() {
+        None
+    } else {
+        Some(unwrap_ptr::<Snapshot>(_env, _snapshot))
+    }
+}
+
+pub(crate) fn get_opt(
+    reader: &dyn Reader,
+    _env: JNIEnv,
+    _cf: JObject,
+    _key: jbyteArray,
+    _snapshot: JObject
+) -> jbyteArray
+{
+    let cf = unwrap_ptr::<ColumnFamily>(&_env, _cf);
+
+    let key = _env.convert_byte_array(_key)
+        .expect("Should be able to convert _key to Rust byte array");
+
+    let snapshot = resolve_snapshot(&_env, _snapshot);
+
+    if let Some(value) = reader.get_cf_opt(cf, key.as_slice(), snapshot){
+        _env.byte_array_from_slice(value.as_slice())
+            .expect("Should be able to convert Rust slice into jbytearray")
+    } else {
+        JObject::null().into_inner()
+    }
+}
+
+pub(crate) fn multi_get_opt(
+    reader: &dyn Reader,
+    _env: JNIEnv,
+    _cf: JObject,
+    _keys: jobjectArray,
+    _snapshot: JObject
+) -> jobject
+{
+    let cf = unwrap_ptr::<ColumnFamily>(&_env, _cf);
+    let keys = java_array_to_vec_byte(&_env, _keys);
+    let snapshot = resolve_snapshot(&_env, _snapshot);
+
+    let key_values = reader.multi_get_cf_opt(
+        cf,
+        keys.iter().map(|k|k.as_slice()).collect::<Vec<_>>().as_slice(),
+        snapshot
+    );
+    map_to_java_map(&_env, &key_values)
+}
+
+pub(crate) fn get_iter_opt(
+    reader: &dyn Reader,
+    _env: JNIEnv,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint,
+    _snapshot: JObject
+) -> jobject
+{
+    let cf = unwrap_ptr::<ColumnFamily>(&_env, _cf);
+
+    let starting_key = match parse_starting_key(&_env, _mode, _starting_key) {
+        Ok(parsed_starting_key) => { parsed_starting_key }
+        Err(e) => {
+            throw!(
+                    &_env, "java/lang/Exception",
+                    format!("Cannot parse the iterator's starting key: {:?}", e).as_str(),
+                    JObject::null().into_inner()
+                );
+        }
+    };
+
+    let mode = match parse_iterator_mode(_mode, starting_key.as_slice(), _direction) {
+        Ok(parsed_mode) => { parsed_mode }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot parse the iterator's mode: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    };
+
+    let snapshot = resolve_snapshot(&_env, _snapshot);
+
+    match reader.get_iter_cf_mode_opt(cf, mode, snapshot){
+        Ok(iter) => {
+            let db_iterator_class = _env.find_class("com/horizen/common/DBIterator")
+                .expect("Should be able to find class DBIterator");
+            create_java_object(&_env, &db_iterator_class, iter)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot get iterator for the specified column family: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
 
 pub(crate) fn get(
     reader: &dyn Reader,
@@ -106,3 +221,46 @@ pub(crate) fn get_iter(
         }
     }
 }
+
+// Bounded iterator over '[_lower, _upper)' (a null bound leaves that side open), pushed down into
+// RocksDB itself via 'Reader::get_range_iter_cf' rather than filtered Java-side
+pub(crate) fn get_range_iter(
+    reader: &dyn Reader,
+    _env: JNIEnv,
+    _cf: JObject,
+    _lower: jbyteArray,
+    _upper: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    let cf = unwrap_ptr::<ColumnFamily>(&_env, _cf);
+
+    let direction = match parse_direction(_direction) {
+        Ok(parsed_direction) => { parsed_direction }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot parse the iterator's direction: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    };
+
+    let lower = parse_bound(&_env, _lower);
+    let upper = parse_bound(&_env, _upper);
+
+    match reader.get_range_iter_cf(cf, lower.as_deref(), upper.as_deref(), direction){
+        Ok(iter) => {
+            let db_iterator_class = _env.find_class("com/horizen/common/DBIterator")
+                .expect("Should be able to find class DBIterator");
+            create_java_object(&_env, &db_iterator_class, iter)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot get range iterator for the specified column family: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use itertools::Itertools;
 use jni::objects::JObject;
 use jni::JNIEnv;
+use jni::sys::{jbyteArray, jboolean};
 use rocksdb::ColumnFamily;
 use crate::common::jni::{unwrap_ptr, exception::_throw_inner, java_list_to_vec_byte};
 use crate::common::transaction::TransactionBasic;
@@ -102,3 +103,32 @@ pub(crate) fn rollback(
         }
     }
 }
+
+pub(crate) fn get_for_update(
+    transaction: &dyn TransactionBasic,
+    _env: JNIEnv,
+    _cf: JObject,
+    _key: jbyteArray,
+    _exclusive: jboolean
+) -> jbyteArray
+{
+    let cf = unwrap_ptr::<ColumnFamily>(&_env, _cf);
+
+    let key = _env.convert_byte_array(_key)
+        .expect("Should be able to convert _key to Rust byte array");
+
+    match transaction.get_for_update_cf(cf, key.as_slice(), _exclusive != 0) {
+        Ok(Some(value)) => {
+            _env.byte_array_from_slice(value.as_slice())
+                .expect("Should be able to convert Rust slice into jbytearray")
+        }
+        Ok(None) => JObject::null().into_inner(),
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot get_for_update the specified key: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
@@ -1,12 +1,14 @@
-use rocksdb::{ColumnFamily, DBVector, Error, IteratorMode, DBIterator, TransactionDB};
+use rocksdb::{ColumnFamily, DBVector, Error, IteratorMode, Direction, DBIterator, TransactionDB, OptimisticTransactionDB, Snapshot, ReadOptions};
 use itertools::Itertools;
-use crate::TransactionInternal;
-use rocksdb::transactions::ops::{Get, GetCF, Iterate, IterateCF};
+use crate::{TransactionInternal, OptimisticTransactionInternal};
+use rocksdb::transactions::ops::{Get, GetCF, Iterate, IterateCF, IterateOpt, IterateCFOpt, GetSnapshot, MultiGet, MultiGetCF};
 use std::collections::HashMap;
 use std::path::Path;
 
 pub mod storage;
 pub mod transaction;
+pub mod backend;
+pub mod integer_keyed_cf;
 #[macro_use]
 pub mod jni;
 
@@ -21,18 +23,37 @@ pub trait InternalRef {
 
     fn transaction_ref(&self) -> Option<&TransactionInternal>;
     fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal>;
+
+    // Methods for accessing an optimistic-mode DB or transaction (OptimisticStorage/OptimisticTransaction)
+    // Default to 'None' so existing pessimistic-mode implementors (Storage/Transaction/StorageVersioned/TransactionVersioned) don't need to change
+
+    fn optimistic_db_ref(&self) -> Option<&OptimisticTransactionDB> { None }
+    fn optimistic_db_ref_mut(&mut self) -> Option<&mut OptimisticTransactionDB> { None }
+
+    fn optimistic_transaction_ref(&self) -> Option<&OptimisticTransactionInternal> { None }
+    fn optimistic_transaction_ref_mut(&mut self) -> Option<&mut OptimisticTransactionInternal> { None }
+
+    // Method for accessing a pinned, read-only point-in-time view (e.g. StorageVersioned::VersionSnapshot)
+    // Defaults to 'None' so existing implementors backed by a DB or transaction don't need to change
+    fn snapshot_ref(&self) -> Option<&Snapshot> { None }
 }
 
 pub trait InternalReader: InternalRef {
 
-    // Wrappers for internal 'get', 'get_cf', 'iterator', 'iterator_cf' methods of TransactionDB and TransactionInternal
-    // These wrappers are used by Reader trait to abstract from concrete object (TransactionDB or TransactionInternal)
+    // Wrappers for internal 'get', 'get_cf', 'iterator', 'iterator_cf' methods of TransactionDB/OptimisticTransactionDB and TransactionInternal/OptimisticTransactionInternal
+    // These wrappers are used by Reader trait to abstract from concrete object (DB or transaction, pessimistic or optimistic)
 
     fn get_internal(&self, key: &[u8]) -> Option<DBVector> {
         if let Some(db) = self.db_ref() {
             db.get(key).ok()?
         } else if let Some(transaction) = self.transaction_ref(){
             transaction.get(key).ok()?
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.get(key).ok()?
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.get(key).ok()?
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.get(key).ok()?
         } else {
             panic!("Unknown type of reference")
         }
@@ -42,6 +63,46 @@ pub trait InternalReader: InternalRef {
             db.get_cf(cf, key).ok()?
         } else if let Some(transaction) = self.transaction_ref(){
             transaction.get_cf(cf, key).ok()?
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.get_cf(cf, key).ok()?
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.get_cf(cf, key).ok()?
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.get_cf(cf, key).ok()?
+        } else {
+            panic!("Unknown type of reference")
+        }
+    }
+    // Batched point-lookup over 'keys' in the 'default' column family, coalescing all of them into a
+    // single native 'multi_get' call (one lock acquisition / one round of I/O scheduling) instead of
+    // 'keys.len()' separate 'get_internal' calls. Values come back in the same order as 'keys'.
+    fn get_multi_internal(&self, keys: &[&[u8]]) -> Vec<Option<DBVector>> {
+        if let Some(db) = self.db_ref() {
+            db.multi_get(keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(transaction) = self.transaction_ref(){
+            transaction.multi_get(keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.multi_get(keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.multi_get(keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.multi_get(keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else {
+            panic!("Unknown type of reference")
+        }
+    }
+    // Same as 'get_multi_internal' but for a specified column family
+    fn get_multi_cf_internal(&self, cf: &ColumnFamily, keys: &[&[u8]]) -> Vec<Option<DBVector>> {
+        if let Some(db) = self.db_ref() {
+            db.multi_get_cf(cf, keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(transaction) = self.transaction_ref(){
+            transaction.multi_get_cf(cf, keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.multi_get_cf(cf, keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.multi_get_cf(cf, keys).into_iter().map(|r| r.ok().flatten()).collect()
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.multi_get_cf(cf, keys).into_iter().map(|r| r.ok().flatten()).collect()
         } else {
             panic!("Unknown type of reference")
         }
@@ -51,6 +112,12 @@ pub trait InternalReader: InternalRef {
             db.iterator(mode)
         } else if let Some(transaction) = self.transaction_ref(){
             transaction.iterator(mode)
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.iterator(mode)
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.iterator(mode)
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.iterator(mode)
         } else {
             panic!("Unknown type of reference")
         }
@@ -60,6 +127,42 @@ pub trait InternalReader: InternalRef {
             db.iterator_cf(cf, mode)
         } else if let Some(transaction) = self.transaction_ref(){
             transaction.iterator_cf(cf, mode)
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.iterator_cf(cf, mode)
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.iterator_cf(cf, mode)
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.iterator_cf(cf, mode)
+        } else {
+            panic!("Unknown type of reference")
+        }
+    }
+    fn iterator_opt_internal(&self, mode: IteratorMode, read_opts: ReadOptions) -> DBIterator {
+        if let Some(db) = self.db_ref() {
+            db.iterator_opt(read_opts, mode)
+        } else if let Some(transaction) = self.transaction_ref(){
+            transaction.iterator_opt(read_opts, mode)
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.iterator_opt(read_opts, mode)
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.iterator_opt(read_opts, mode)
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.iterator_opt(read_opts, mode)
+        } else {
+            panic!("Unknown type of reference")
+        }
+    }
+    fn iterator_cf_opt_internal(&self, cf: &ColumnFamily, mode: IteratorMode, read_opts: ReadOptions) -> Result<DBIterator, Error> {
+        if let Some(db) = self.db_ref(){
+            db.iterator_cf_opt(cf, read_opts, mode)
+        } else if let Some(transaction) = self.transaction_ref(){
+            transaction.iterator_cf_opt(cf, read_opts, mode)
+        } else if let Some(db) = self.optimistic_db_ref() {
+            db.iterator_cf_opt(cf, read_opts, mode)
+        } else if let Some(transaction) = self.optimistic_transaction_ref() {
+            transaction.iterator_cf_opt(cf, read_opts, mode)
+        } else if let Some(snapshot) = self.snapshot_ref() {
+            snapshot.iterator_cf_opt(cf, read_opts, mode)
         } else {
             panic!("Unknown type of reference")
         }
@@ -82,19 +185,25 @@ pub trait Reader: InternalReader {
 
     // Gets KV pairs for a specified list of keys in the 'default' column family from an underlying storage;
     // For the absent keys the Values in corresponding KV pairs are None.
+    // Dispatches to a single native 'get_multi_internal' call rather than looping 'get' per key.
     fn multi_get(&self, keys: &[&[u8]]) -> HashMap<Vec<u8>, Option<Vec<u8>>> {
-        keys.iter()
-            .unique()
-            .map(|&key| (key.to_vec(), self.get(key)))
+        let deduped_keys: Vec<&[u8]> = keys.iter().unique().copied().collect();
+        let values = self.get_multi_internal(&deduped_keys);
+        deduped_keys.into_iter()
+            .map(|key| key.to_vec())
+            .zip(values.into_iter().map(|v| v.map(|dbv| dbv.to_vec())))
             .collect()
     }
 
     // Gets KV pairs for a specified list of keys in a specified column family from an underlying storage;
     // For the absent keys the Values in corresponding KV pairs are None.
+    // Dispatches to a single native 'get_multi_cf_internal' call rather than looping 'get_cf' per key.
     fn multi_get_cf(&self, cf: &ColumnFamily, keys: &[&[u8]]) -> HashMap<Vec<u8>, Option<Vec<u8>>> {
-        keys.iter()
-            .unique()
-            .map(|&key| (key.to_vec(), self.get_cf(cf, key)))
+        let deduped_keys: Vec<&[u8]> = keys.iter().unique().copied().collect();
+        let values = self.get_multi_cf_internal(cf, &deduped_keys);
+        deduped_keys.into_iter()
+            .map(|key| key.to_vec())
+            .zip(values.into_iter().map(|v| v.map(|dbv| dbv.to_vec())))
             .collect()
     }
 
@@ -123,6 +232,131 @@ pub trait Reader: InternalReader {
     fn is_empty_cf(&self, cf: &ColumnFamily) -> Result<bool, Error> {
         Ok(self.get_iter_cf(cf)?.next().is_none())
     }
+
+    // Pins a consistent, point-in-time view of the underlying DB: reads issued against the returned
+    // Snapshot (see 'get_cf_opt'/'multi_get_cf_opt'/'get_iter_cf_mode_opt') keep observing this fixed
+    // state regardless of concurrent writes made afterwards, without the overhead of a read transaction.
+    // Returns None when this Reader is backed by a Transaction rather than a DB directly - only
+    // Storage/StorageVersioned and their optimistic-mode counterparts can be snapshotted this way;
+    // see 'Storage::create_transaction_with_snapshot' for pinning a view for a single transaction's lifetime.
+    fn snapshot(&self) -> Option<Snapshot> {
+        if let Some(db) = self.db_ref() {
+            Some(db.snapshot())
+        } else {
+            self.optimistic_db_ref().map(|db| db.snapshot())
+        }
+    }
+
+    // Same as 'get_cf' but reads through 'snapshot' instead of the live DB state when one is given
+    fn get_cf_opt(&self, cf: &ColumnFamily, key: &[u8], snapshot: Option<&Snapshot>) -> Option<Vec<u8>> {
+        match snapshot {
+            Some(snapshot) => snapshot.get_cf(cf, key).ok()?.map(|v| v.to_vec()),
+            None => self.get_cf(cf, key),
+        }
+    }
+
+    // Same as 'multi_get_cf' but reads through 'snapshot' instead of the live DB state when one is given
+    fn multi_get_cf_opt(&self, cf: &ColumnFamily, keys: &[&[u8]], snapshot: Option<&Snapshot>) -> HashMap<Vec<u8>, Option<Vec<u8>>> {
+        keys.iter()
+            .unique()
+            .map(|&key| (key.to_vec(), self.get_cf_opt(cf, key, snapshot)))
+            .collect()
+    }
+
+    // Same as 'get_iter_cf_mode' but iterates through 'snapshot' instead of the live DB state when one is given
+    fn get_iter_cf_mode_opt(&self, cf: &ColumnFamily, mode: IteratorMode, snapshot: Option<&Snapshot>) -> Result<DBIterator, Error> {
+        match snapshot {
+            Some(snapshot) => snapshot.iterator_cf(cf, mode),
+            None => self.get_iter_cf_mode(cf, mode),
+        }
+    }
+
+    // Returns a lazy iterator of every (key, value) pair in 'cf' whose key starts with 'prefix', in key
+    // order, so a "all keys under namespace X" query costs proportionally to the number of matching keys
+    // rather than the whole column family. Built on 'get_iter_cf_mode(cf, IteratorMode::From(prefix, ..))'
+    // plus a 'take_while' cutoff once a key stops matching, rather than materializing 'get_all_cf' and
+    // filtering afterwards. If 'cf' was created via
+    // 'ColumnFamiliesManager::set_column_family_with_prefix_extractor' with a prefix length covering
+    // 'prefix', RocksDB's own bloom/prefix-bucket filtering also narrows which SST blocks this has to read.
+    // Returns Result with Error if 'cf' can't be iterated (e.g. absent)
+    fn iter_prefix<'a>(&'a self, cf: &'a ColumnFamily, prefix: &'a [u8]) -> Result<Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>, Error> {
+        let iter = self.get_iter_cf_mode(cf, IteratorMode::From(prefix, Direction::Forward))?;
+        Ok(Box::new(iter.take_while(move |(key, _)| key.starts_with(prefix))))
+    }
+
+    // Returns a lazy iterator of every (key, value) pair in 'cf' with 'start <= key < end', in key order.
+    // Same cost characteristics as 'iter_prefix': proportional to the size of the range, not the column family.
+    // Returns Result with Error if 'cf' can't be iterated (e.g. absent)
+    fn iter_range<'a>(&'a self, cf: &'a ColumnFamily, start: &'a [u8], end: &'a [u8]) -> Result<Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a>, Error> {
+        let iter = self.get_iter_cf_mode(cf, IteratorMode::From(start, Direction::Forward))?;
+        Ok(Box::new(iter.take_while(move |(key, _)| key.as_ref() < end)))
+    }
+
+    // Returns an iterator over 'cf' bounded to '[lower, upper)' ('None' leaves that side unbounded), unlike
+    // 'iter_range' pushes the bounds down into RocksDB itself via 'ReadOptions::set_iterate_lower_bound'/
+    // 'set_iterate_upper_bound' rather than filtering in a 'take_while' after the fact, so the engine can
+    // skip whole SST blocks outside the range instead of merely stopping the Rust-side iteration early.
+    // Returns Result with Error if 'cf' can't be iterated (e.g. absent)
+    fn get_range_iter_cf(&self, cf: &ColumnFamily, lower: Option<&[u8]>, upper: Option<&[u8]>, direction: Direction) -> Result<DBIterator, Error> {
+        let mut read_opts = ReadOptions::default();
+        if let Some(lower) = lower {
+            read_opts.set_iterate_lower_bound(lower.to_vec());
+        }
+        if let Some(upper) = upper {
+            read_opts.set_iterate_upper_bound(upper.to_vec());
+        }
+
+        let mode = match direction {
+            Direction::Forward => lower.map_or(IteratorMode::Start, |lower| IteratorMode::From(lower, Direction::Forward)),
+            Direction::Reverse => upper.map_or(IteratorMode::End, |upper| IteratorMode::From(upper, Direction::Reverse)),
+        };
+        self.iterator_cf_opt_internal(cf, mode, read_opts)
+    }
+
+    // Same as 'get_range_iter_cf' but over the 'default' column family
+    fn get_range_iter(&self, lower: Option<&[u8]>, upper: Option<&[u8]>, direction: Direction) -> DBIterator {
+        let mut read_opts = ReadOptions::default();
+        if let Some(lower) = lower {
+            read_opts.set_iterate_lower_bound(lower.to_vec());
+        }
+        if let Some(upper) = upper {
+            read_opts.set_iterate_upper_bound(upper.to_vec());
+        }
+
+        let mode = match direction {
+            Direction::Forward => lower.map_or(IteratorMode::Start, |lower| IteratorMode::From(lower, Direction::Forward)),
+            Direction::Reverse => upper.map_or(IteratorMode::End, |upper| IteratorMode::From(upper, Direction::Reverse)),
+        };
+        self.iterator_opt_internal(mode, read_opts)
+    }
+
+    // Same as 'iter_prefix', but pushes the bound down into RocksDB via 'get_range_iter_cf' instead of
+    // filtering with 'take_while': the exclusive upper bound is derived from 'prefix' by incrementing its
+    // last byte that isn't already 0xFF and dropping everything after it (the smallest key that's no longer
+    // prefixed by 'prefix'). A prefix that's empty or made up entirely of 0xFF bytes has no finite upper
+    // bound, so the scan is left open-ended on that side.
+    // Returns Result with Error if 'cf' can't be iterated (e.g. absent)
+    fn get_prefix_iter_cf(&self, cf: &ColumnFamily, prefix: &[u8]) -> Result<DBIterator, Error> {
+        let upper_bound = increment_prefix(prefix);
+        self.get_range_iter_cf(cf, Some(prefix), upper_bound.as_deref(), Direction::Forward)
+    }
+}
+
+// Derives the exclusive upper bound for a server-side prefix scan: the smallest key greater than every key
+// starting with 'prefix', by incrementing the last byte that isn't already 0xFF and dropping everything
+// after it. Returns None when 'prefix' is empty or consists only of 0xFF bytes, i.e. there's no finite
+// upper bound short of scanning to the end of the column family.
+fn increment_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut upper_bound = prefix.to_vec();
+    while let Some(&last) = upper_bound.last() {
+        if last == 0xFF {
+            upper_bound.pop();
+        } else {
+            *upper_bound.last_mut().unwrap() += 1;
+            return Some(upper_bound);
+        }
+    }
+    None
 }
 
 // Removes the specified directory by deleting it together with all nested subdirectories
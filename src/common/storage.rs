@@ -1,16 +1,20 @@
-use rocksdb::{ColumnFamily, Options, Error};
-use rocksdb::transactions::ops::{GetColumnFamilies, CreateCf};
-use crate::common::InternalRef;
+use rocksdb::{ColumnFamily, Options, SliceTransform, Error};
+use rocksdb::transactions::ops::{GetColumnFamilies, CreateCf, DropCf};
+use crate::common::{InternalRef, Reader};
+use crate::common::integer_keyed_cf::IntegerKeyedCf;
 
 pub trait ColumnFamiliesManager: InternalRef {
 
-    // Trait for managing column families of Storage/StorageVersioned
+    // Trait for managing column families of Storage/StorageVersioned (both pessimistic and optimistic mode)
 
     // Returns a handle for a specified column family name
     // Returns None if CF with a specified name is absent in storage
     fn get_column_family(&self, cf_name: &str) -> Option<&ColumnFamily> {
-        self.db_ref()?
-            .cf_handle(cf_name)
+        if let Some(db) = self.db_ref() {
+            db.cf_handle(cf_name)
+        } else {
+            self.optimistic_db_ref()?.cf_handle(cf_name)
+        }
     }
 
     // Creates column family with a specified name
@@ -18,13 +22,57 @@ pub trait ColumnFamiliesManager: InternalRef {
     // Returns Err with describing message if any error occurred during column family creation
     fn set_column_family(&mut self, cf_name: &str) -> Result<(), Error>{
         if self.get_column_family(cf_name).is_none(){
-            self.db_ref_mut().ok_or(Error::new("No mutable reference for db".into()))?
-                .create_cf(cf_name, &Options::default())
+            if let Some(db) = self.db_ref_mut() {
+                db.create_cf(cf_name, &Options::default())
+            } else {
+                self.optimistic_db_ref_mut().ok_or(Error::new("No mutable reference for db".into()))?
+                    .create_cf(cf_name, &Options::default())
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    // Same as 'set_column_family' but additionally configures a fixed-length prefix extractor on the new
+    // column family, so 'Reader::iter_prefix' scans over it benefit from RocksDB's bloom/prefix-bucket
+    // filtering rather than a full column-family scan. A no-op (besides creating the CF) if it already
+    // exists, matching 'set_column_family's existing idempotency.
+    // Returns Err with describing message if any error occurred during column family creation
+    fn set_column_family_with_prefix_extractor(&mut self, cf_name: &str, prefix_len: usize) -> Result<(), Error>{
+        if self.get_column_family(cf_name).is_none(){
+            let mut opts = Options::default();
+            opts.set_prefix_extractor(SliceTransform::create_fixed_prefix(prefix_len));
+            if let Some(db) = self.db_ref_mut() {
+                db.create_cf(cf_name, &opts)
+            } else {
+                self.optimistic_db_ref_mut().ok_or(Error::new("No mutable reference for db".into()))?
+                    .create_cf(cf_name, &opts)
+            }
         } else {
             Ok(())
         }
     }
 
-    // TODO: DropCF trait currently is not implemented for TransactionDB
-    // fn delete_column_family(&self, cf_name: &str) -> bool;
+    // Drops column family with a specified name
+    // Returns Ok if the column family was dropped successfully or was already absent
+    // Returns Err with describing message if any error occurred while dropping the column family
+    fn delete_column_family(&mut self, cf_name: &str) -> Result<(), Error>{
+        if self.get_column_family(cf_name).is_none(){
+            return Ok(())
+        }
+        if let Some(db) = self.db_ref_mut() {
+            db.drop_cf(cf_name)
+        } else {
+            self.optimistic_db_ref_mut().ok_or(Error::new("No mutable reference for db".into()))?
+                .drop_cf(cf_name)
+        }
+    }
+
+    // Returns a typed, 'u64'-keyed view over the column family named 'cf_name' (see 'IntegerKeyedCf'),
+    // or None if that column family doesn't exist. Every existing implementor of this trait
+    // (Storage/StorageVersioned and their optimistic-mode counterparts) is also a 'Reader', which is all
+    // 'IntegerKeyedCf's read-side methods need; its write-side methods additionally require 'TransactionBasic'.
+    fn integer_keyed_cf<'a>(&'a self, cf_name: &str) -> Option<IntegerKeyedCf<'a, Self>> where Self: Reader + Sized {
+        Some(IntegerKeyedCf::new(self, self.get_column_family(cf_name)?))
+    }
 }
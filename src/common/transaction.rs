@@ -1,18 +1,141 @@
-use rocksdb::{ColumnFamily, Error};
-use rocksdb::transactions::ops::{PutCF, DeleteCF, Put, Delete};
+use rocksdb::{ColumnFamily, DBVector, Error, TransactionOptions, WriteOptions};
+use rocksdb::transactions::ops::{PutCF, DeleteCF, Put, Delete, GetForUpdateCF};
 use crate::common::{Reader, InternalRef};
+use crate::{TransactionInternal, OptimisticTransactionInternal};
+
+// Tunable parameters for a single transaction, passed to e.g. 'Storage::create_transaction_with_options'/
+// 'StorageVersioned::create_transaction_with_options'. 'lock_timeout_ms' of -1 falls back to the
+// DB-wide transaction lock timeout (see 'StorageLockingOptions::transaction_lock_timeout_ms').
+pub struct TransactionLockOptions {
+    pub snapshot: bool,
+    pub lock_timeout_ms: i64,
+    pub deadlock_detect: bool,
+    pub deadlock_detect_depth: i64,
+}
+
+impl Default for TransactionLockOptions {
+    fn default() -> Self {
+        TransactionLockOptions{ snapshot: false, lock_timeout_ms: -1, deadlock_detect: false, deadlock_detect_depth: 50 }
+    }
+}
+
+impl TransactionLockOptions {
+    pub(crate) fn to_transaction_options(&self) -> TransactionOptions {
+        let mut opts = TransactionOptions::default();
+        opts.set_snapshot(self.snapshot);
+        opts.set_lock_timeout(self.lock_timeout_ms);
+        opts.set_deadlock_detect(self.deadlock_detect);
+        opts.set_deadlock_detect_depth(self.deadlock_detect_depth);
+        opts
+    }
+}
+
+// Tunable write-durability parameters for a single transaction's eventual commit, passed to e.g.
+// 'Storage::create_transaction_with_write_options'. RocksDB ties a transaction's WAL/fsync behavior to
+// the 'WriteOptions' it was created with (not to any options passed at 'commit()' time), so these are
+// applied once, up front, the same way 'TransactionLockOptions' is. Defaults reproduce 'WriteOptions::
+// default()', i.e. the write behavior every other 'create_transaction*' constructor already has: the WAL
+// is written and 'commit()' doesn't block on an fsync.
+pub struct TransactionWriteOptions {
+    pub sync: bool,
+    pub disable_wal: bool,
+}
+
+impl Default for TransactionWriteOptions {
+    fn default() -> Self {
+        TransactionWriteOptions{ sync: false, disable_wal: false }
+    }
+}
+
+impl TransactionWriteOptions {
+    pub(crate) fn to_write_options(&self) -> WriteOptions {
+        let mut opts = WriteOptions::default();
+        opts.set_sync(self.sync);
+        opts.set_disable_wal(self.disable_wal);
+        opts
+    }
+}
+
+// Abstracts over the two kinds of underlying RocksDB transaction a 'TransactionBasic' implementor can wrap:
+// the pessimistic 'TransactionInternal' (from a TransactionDB, takes row locks eagerly)
+// and the optimistic 'OptimisticTransactionInternal' (from an OptimisticTransactionDB, conflicts are only detected at commit time).
+// This lets 'update'/'save'/'rollback*' below be written once instead of duplicated per backend.
+enum TransactionRef<'a> {
+    Pessimistic(&'a TransactionInternal),
+    Optimistic(&'a OptimisticTransactionInternal),
+}
+
+impl<'a> TransactionRef<'a> {
+    fn put(&self, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        match self {
+            Self::Pessimistic(t) => t.put(key, value),
+            Self::Optimistic(t) => t.put(key, value),
+        }
+    }
+    fn delete(&self, key: &[u8]) -> Result<(), Error> {
+        match self {
+            Self::Pessimistic(t) => t.delete(key),
+            Self::Optimistic(t) => t.delete(key),
+        }
+    }
+    fn put_cf(&self, cf: &ColumnFamily, key: &[u8], value: &[u8]) -> Result<(), Error> {
+        match self {
+            Self::Pessimistic(t) => t.put_cf(cf, key, value),
+            Self::Optimistic(t) => t.put_cf(cf, key, value),
+        }
+    }
+    fn delete_cf(&self, cf: &ColumnFamily, key: &[u8]) -> Result<(), Error> {
+        match self {
+            Self::Pessimistic(t) => t.delete_cf(cf, key),
+            Self::Optimistic(t) => t.delete_cf(cf, key),
+        }
+    }
+    fn set_savepoint(&self) {
+        match self {
+            Self::Pessimistic(t) => t.set_savepoint(),
+            Self::Optimistic(t) => t.set_savepoint(),
+        }
+    }
+    fn rollback_to_savepoint(&self) -> Result<(), Error> {
+        match self {
+            Self::Pessimistic(t) => t.rollback_to_savepoint(),
+            Self::Optimistic(t) => t.rollback_to_savepoint(),
+        }
+    }
+    fn rollback(&self) -> Result<(), Error> {
+        match self {
+            Self::Pessimistic(t) => t.rollback(),
+            Self::Optimistic(t) => t.rollback(),
+        }
+    }
+    fn get_for_update_cf(&self, cf: &ColumnFamily, key: &[u8], exclusive: bool) -> Result<Option<DBVector>, Error> {
+        match self {
+            Self::Pessimistic(t) => t.get_for_update_cf(cf, key, exclusive),
+            Self::Optimistic(t) => t.get_for_update_cf(cf, key, exclusive),
+        }
+    }
+}
 
 pub trait TransactionBasic: Reader + InternalRef {
 
     const NO_REF: &'static str = "No reference for transaction";
     const NO_REF_MUT: &'static str = "No mutable reference for transaction";
 
+    // Returns the underlying pessimistic or optimistic transaction reference, whichever this implementor wraps
+    fn transaction_ref_any(&self) -> Option<TransactionRef> {
+        if let Some(t) = self.transaction_ref() {
+            Some(TransactionRef::Pessimistic(t))
+        } else {
+            self.optimistic_transaction_ref().map(TransactionRef::Optimistic)
+        }
+    }
+
     // Performs the specified insertions ('to_update' vector of KVs) and removals ('to_delete' vector of Keys) for the 'default' column family in a current transaction
     // Returns Result with error message if any error occurred
     fn update(&self,
               to_update: &Vec<(&[u8], &[u8])>,
               to_delete: &Vec<&[u8]>) -> Result<(), Error> {
-        let transaction = self.transaction_ref().ok_or(Error::new(Self::NO_REF.into()))?;
+        let transaction = self.transaction_ref_any().ok_or(Error::new(Self::NO_REF.into()))?;
         for &kv in to_update {
             transaction.put(kv.0, kv.1)?
         }
@@ -28,7 +151,7 @@ pub trait TransactionBasic: Reader + InternalRef {
               cf: &ColumnFamily,
               to_update: &Vec<(&[u8], &[u8])>,
               to_delete: &Vec<&[u8]>) -> Result<(), Error> {
-        let transaction = self.transaction_ref().ok_or(Error::new(Self::NO_REF.into()))?;
+        let transaction = self.transaction_ref_any().ok_or(Error::new(Self::NO_REF.into()))?;
         for &kv in to_update {
             transaction.put_cf(cf, kv.0, kv.1)?
         }
@@ -40,20 +163,39 @@ pub trait TransactionBasic: Reader + InternalRef {
 
     // Saves the current state of a transaction to which it can be rolled back later
     fn save(&self) -> Result<(), Error>{
-        let transaction = self.transaction_ref().ok_or(Error::new(Self::NO_REF.into()))?;
+        let transaction = self.transaction_ref_any().ok_or(Error::new(Self::NO_REF.into()))?;
         Ok(transaction.set_savepoint())
     }
 
     // Rolls back the current state of a transaction to the most recent savepoint.
     // Can be performed sequentially thus restoring previous savepoints in LIFO order.
     fn rollback_to_savepoint(&self) -> Result<(), Error>{
-        let transaction = self.transaction_ref().ok_or(Error::new(Self::NO_REF.into()))?;
+        let transaction = self.transaction_ref_any().ok_or(Error::new(Self::NO_REF.into()))?;
         transaction.rollback_to_savepoint()
     }
 
     // Rolls back transaction to the initial state (state at the moment when transaction was started)
     fn rollback(&self) -> Result<(), Error>{
-        let transaction = self.transaction_ref().ok_or(Error::new(Self::NO_REF.into()))?;
+        let transaction = self.transaction_ref_any().ok_or(Error::new(Self::NO_REF.into()))?;
         transaction.rollback()
     }
+
+    // Reads the current value for 'key' in column family 'cf' while registering the key for commit-time
+    // conflict validation: if this transaction was started with a pinned snapshot (see
+    // 'Storage::create_transaction_with_snapshot') and another transaction commits a change to this key
+    // before this one commits, 'commit()' fails with a conflict error instead of silently overwriting the
+    // concurrent update. 'exclusive' requests an exclusive (write) intent for the key rather than a shared one.
+    // Returns Ok(None) if the key is absent, Err if there is no underlying transaction reference.
+    fn get_for_update_cf(&self, cf: &ColumnFamily, key: &[u8], exclusive: bool) -> Result<Option<Vec<u8>>, Error> {
+        let transaction = self.transaction_ref_any().ok_or(Error::new(Self::NO_REF.into()))?;
+        Ok(transaction.get_for_update_cf(cf, key, exclusive)?.map(|v| v.to_vec()))
+    }
+}
+
+// Returns true if a commit failure reported by RocksDB was caused by a write-write conflict detected only at
+// commit time (e.g. for an OptimisticTransactionDB, where no locks are held while the transaction accumulates updates)
+// rather than some other kind of failure, so callers know whether retrying the transaction makes sense.
+pub fn is_conflict_error(error: &Error) -> bool {
+    let message = error.to_string().to_lowercase();
+    message.contains("busy") || message.contains("conflict") || message.contains("try again")
 }
\ No newline at end of file
@@ -31,6 +31,10 @@ private_in_public
 )]
 
 pub use rocksdb::Transaction as TransactionInternal;
+pub use rocksdb::OptimisticTransaction as OptimisticTransactionInternal;
+// Transactional write batch: accumulates puts/deletes across column families and commits them in a single
+// atomic write against a TransactionDB, bypassing per-key transaction/lock bookkeeping.
+pub type WriteBatch = rocksdb::WriteBatchWithTransaction<true>;
 #[macro_use]
 pub mod common;
 
@@ -0,0 +1,278 @@
+use rocksdb::Error;
+use rocksdb::transactions::ops::CreateCheckpointObject;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::common::join_path_strings;
+use crate::storage::Storage;
+
+// Append-only, tab-separated log of committed backups within a 'backup_dir', in the same spirit as
+// 'storage_versioned::manifest::VersionManifest': one line per 'create_backup' call, read back in full
+// by 'get_backup_info'/'purge_old_backups' rather than kept in memory, since a 'backup_dir' is meant to
+// be inspected and managed across process restarts (and possibly by a different Storage instance than
+// the one that wrote it - 'restore_from_backup'/'get_backup_info'/'purge_old_backups' are standalone
+// functions for exactly that reason).
+//
+// Deliberately NOT done here: the content-hash deduplication 'storage_versioned' uses to let identical
+// versions alias the same checkpoint directory. A backup's "incremental" property instead comes directly
+// from 'create_checkpoint' itself - hard-linking unchanged SST files rather than copying them - so two
+// successive backups of a mostly-unchanged DB already share inodes and cost almost no extra disk, without
+// needing a content index of their own. Deduplicating *across* non-adjacent backups on a different
+// filesystem than the live DB (where 'create_checkpoint' falls back to a full copy) is a real gap this
+// does not close; it would need the same kind of content-hash bookkeeping 'storage_versioned' has, and is
+// left for a future pass rather than duplicated here speculatively.
+const BACKUP_MANIFEST_FILE_NAME: &str = "BACKUP_MANIFEST";
+
+// A single backup recorded in a 'backup_dir': its id (assigned sequentially, starting at 1), the unix
+// timestamp (seconds) it was taken at, and the on-disk size of its checkpoint directory in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BackupInfo {
+    pub backup_id: u32,
+    pub timestamp: u64,
+    pub size: u64,
+}
+
+fn manifest_path(backup_dir: &str) -> Result<String, Error> {
+    join_path_strings(backup_dir, BACKUP_MANIFEST_FILE_NAME)
+}
+
+fn backup_path(backup_dir: &str, backup_id: u32) -> Result<String, Error> {
+    join_path_strings(backup_dir, backup_id.to_string().as_str())
+}
+
+// Reads back every backup recorded so far in 'backup_dir', oldest first
+// Returns an empty list (rather than an error) if 'backup_dir' doesn't contain a manifest yet
+fn read_backup_info(backup_dir: &str) -> Result<Vec<BackupInfo>, Error> {
+    let path = manifest_path(backup_dir)?;
+    if !Path::new(path.as_str()).exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = File::open(path.as_str())
+        .map_err(|e| Error::new(format!("Can't open the backup manifest: {:?}", e)))?;
+
+    let mut infos = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| Error::new(format!("Can't read the backup manifest: {:?}", e)))?;
+        let mut parts = line.splitn(3, '\t');
+        if let (Some(backup_id), Some(timestamp), Some(size)) = (parts.next(), parts.next(), parts.next()) {
+            if let (Ok(backup_id), Ok(timestamp), Ok(size)) = (backup_id.parse(), timestamp.parse(), size.parse()) {
+                infos.push(BackupInfo{ backup_id, timestamp, size });
+            }
+        }
+        // A malformed or partially-written line (e.g. a crash mid-append) is simply skipped, the same
+        // way 'VersionEdit::deserialize' skips one in the versioned manifest's recovery path.
+    }
+    Ok(infos)
+}
+
+fn append_backup_info(backup_dir: &str, info: &BackupInfo) -> Result<(), Error> {
+    let path = manifest_path(backup_dir)?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path.as_str())
+        .map_err(|e| Error::new(format!("Can't open the backup manifest: {:?}", e)))?;
+    file.write_all(format!("{}\t{}\t{}\n", info.backup_id, info.timestamp, info.size).as_bytes())
+        .map_err(|e| Error::new(format!("Can't append to the backup manifest: {:?}", e)))?;
+    file.sync_all().map_err(|e| Error::new(format!("Can't fsync the backup manifest: {:?}", e)))
+}
+
+fn rewrite_backup_info(backup_dir: &str, infos: &[BackupInfo]) -> Result<(), Error> {
+    let path = manifest_path(backup_dir)?;
+    let mut file = File::create(path.as_str())
+        .map_err(|e| Error::new(format!("Can't rewrite the backup manifest: {:?}", e)))?;
+    for info in infos {
+        file.write_all(format!("{}\t{}\t{}\n", info.backup_id, info.timestamp, info.size).as_bytes())
+            .map_err(|e| Error::new(format!("Can't rewrite the backup manifest: {:?}", e)))?;
+    }
+    file.sync_all().map_err(|e| Error::new(format!("Can't fsync the backup manifest: {:?}", e)))
+}
+
+fn dir_size(path: &Path) -> Result<u64, Error> {
+    let mut size = 0u64;
+    for entry in fs::read_dir(path).map_err(|e| Error::new(format!("Can't read backup directory: {:?}", e)))? {
+        let entry = entry.map_err(|e| Error::new(format!("Can't read backup directory entry: {:?}", e)))?;
+        let metadata = entry.metadata().map_err(|e| Error::new(format!("Can't read backup entry metadata: {:?}", e)))?;
+        size += if metadata.is_dir() { dir_size(entry.path().as_path())? } else { metadata.len() };
+    }
+    Ok(size)
+}
+
+fn copy_dir_all(source: &Path, target: &Path) -> Result<(), Error> {
+    fs::create_dir_all(target).map_err(|e| Error::new(format!("Can't create restore directory: {:?}", e)))?;
+    for entry in fs::read_dir(source).map_err(|e| Error::new(format!("Can't read backup directory: {:?}", e)))? {
+        let entry = entry.map_err(|e| Error::new(format!("Can't read backup directory entry: {:?}", e)))?;
+        let target_entry = target.join(entry.file_name());
+        let metadata = entry.metadata().map_err(|e| Error::new(format!("Can't read backup entry metadata: {:?}", e)))?;
+        if metadata.is_dir() {
+            copy_dir_all(entry.path().as_path(), target_entry.as_path())?;
+        } else {
+            fs::copy(entry.path(), target_entry.as_path())
+                .map_err(|e| Error::new(format!("Can't copy backup file: {:?}", e)))?;
+        }
+    }
+    Ok(())
+}
+
+impl Storage {
+    // Takes a new backup of this Storage's current state into 'backup_dir', assigning it the next
+    // sequential backup id (starting at 1) and recording its timestamp and on-disk size in
+    // 'backup_dir's manifest. Internally this is just 'create_checkpoint' into a per-id subdirectory of
+    // 'backup_dir', so successive backups of a mostly-unchanged DB on the same filesystem share SST
+    // files via hard link rather than duplicating them - see the module-level doc comment for what that
+    // does and doesn't cover.
+    // NOT safe to call concurrently (from multiple threads/processes) against the same 'backup_dir':
+    // 'next_id' is read from the manifest and the checkpoint directory for it is created afterwards, with
+    // no lock claiming that id in between, so two racing calls can compute the same 'next_id' and the
+    // loser's 'create_checkpoint' fails outright (it refuses to write into an already-existing directory)
+    // instead of retrying with a fresh id. Callers that need concurrent backups must serialize their own
+    // 'create_backup' calls per 'backup_dir'.
+    // Returns the newly created backup's id, or Err with a describing message if some error occurred
+    pub fn create_backup(&self, backup_dir: &str) -> Result<u32, Error> {
+        fs::create_dir_all(backup_dir)
+            .map_err(|e| Error::new(format!("Can't create the backup directory: {:?}", e)))?;
+
+        let next_id = read_backup_info(backup_dir)?.iter()
+            .map(|info| info.backup_id)
+            .max()
+            .map_or(1, |max_id| max_id + 1);
+
+        let path = backup_path(backup_dir, next_id)?;
+        // The checkpoint API creates only its own target directory, not the per-id directory above it
+        fs::create_dir_all(path.as_str())
+            .map_err(|e| Error::new(format!("Can't create the backup's directory: {:?}", e)))?;
+        let db_path = join_path_strings(path.as_str(), Self::DB_DIR)?;
+        self.db.create_checkpoint_object()?.create_checkpoint(db_path)?;
+
+        let size = dir_size(Path::new(path.as_str()))?;
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map_err(|e| Error::new(format!("System clock error: {:?}", e)))?
+            .as_secs();
+
+        append_backup_info(backup_dir, &BackupInfo{ backup_id: next_id, timestamp, size })?;
+        Ok(next_id)
+    }
+
+    // Restores a backup from 'backup_dir' into 'restore_path', which can then be opened directly via
+    // 'Storage::open(restore_path, false)'. 'backup_id' selects which backup to restore; 'None' restores
+    // the most recent one. Unlike 'create_backup', this always performs a full copy rather than relying
+    // on hard links, since 'restore_path' is typically meant to be usable independently of 'backup_dir'
+    // (e.g. to seed a new node) - hard-linking would leave the restored copy sharing inodes with a backup
+    // directory that may later be purged.
+    // Returns Err with a describing message if the requested backup doesn't exist or some other error occurred
+    pub fn restore_from_backup(backup_dir: &str, restore_path: &str, backup_id: Option<u32>) -> Result<(), Error> {
+        let infos = read_backup_info(backup_dir)?;
+        let id = match backup_id {
+            Some(id) => id,
+            None => infos.iter().map(|info| info.backup_id).max()
+                .ok_or_else(|| Error::new("No backups found in the given backup_dir".into()))?
+        };
+
+        if !infos.iter().any(|info| info.backup_id == id) {
+            return Err(Error::new(format!("Backup {} not found in the given backup_dir", id)));
+        }
+
+        let source = backup_path(backup_dir, id)?;
+        copy_dir_all(Path::new(source.as_str()), Path::new(restore_path))
+    }
+
+    // Lists every backup recorded in 'backup_dir' (id, timestamp, size), oldest first
+    // Returns Err with a describing message if the manifest couldn't be read
+    pub fn get_backup_info(backup_dir: &str) -> Result<Vec<BackupInfo>, Error> {
+        read_backup_info(backup_dir)
+    }
+
+    // Removes the oldest backups in 'backup_dir' until at most 'num_to_keep' remain
+    // Returns Err with a describing message if some error occurred
+    pub fn purge_old_backups(backup_dir: &str, num_to_keep: usize) -> Result<(), Error> {
+        let mut infos = read_backup_info(backup_dir)?;
+        infos.sort_by_key(|info| info.backup_id);
+
+        if infos.len() <= num_to_keep {
+            return Ok(());
+        }
+
+        let remaining = infos.split_off(infos.len() - num_to_keep);
+        for info in &infos {
+            let path = backup_path(backup_dir, info.backup_id)?;
+            fs::remove_dir_all(path.as_str())
+                .map_err(|e| Error::new(format!("Can't remove backup directory: {:?}", e)))?;
+        }
+        rewrite_backup_info(backup_dir, &remaining)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::storage::Storage;
+    use crate::storage::backup::BackupInfo;
+    use crate::common::{Reader, test_dir};
+
+    #[test]
+    fn storage_backup_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_backup_tests").unwrap();
+        let (_backup_tmp_dir, backup_dir) = test_dir("storage_backup_tests_backups").unwrap();
+        let (_restore_tmp_dir, restore_path) = test_dir("storage_backup_tests_restore").unwrap();
+        // 'test_dir' itself creates 'restore_path', but 'copy_dir_all' just re-populates it - unlike
+        // 'create_checkpoint' it doesn't require the target to be absent.
+        std::fs::remove_dir_all(restore_path.as_str()).unwrap();
+
+        let storage = Storage::open(storage_path.as_str(), true).unwrap();
+        let tx = storage.create_transaction().unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit().unwrap();
+
+        let backup_id1 = storage.create_backup(backup_dir.as_str()).unwrap();
+        assert_eq!(backup_id1, 1);
+
+        let tx2 = storage.create_transaction().unwrap();
+        tx2.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx2.commit().unwrap();
+
+        let backup_id2 = storage.create_backup(backup_dir.as_str()).unwrap();
+        assert_eq!(backup_id2, 2);
+
+        let infos = Storage::get_backup_info(backup_dir.as_str()).unwrap();
+        assert_eq!(infos.iter().map(|info| info.backup_id).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(infos.iter().all(|info: &BackupInfo| info.size > 0));
+
+        // restoring without a 'backup_id' picks the most recent backup
+        Storage::restore_from_backup(backup_dir.as_str(), restore_path.as_str(), None).unwrap();
+        let restored = Storage::open(restore_path.as_str(), false).unwrap();
+        assert_eq!(restored.get(b"k1").unwrap(), b"v2");
+        drop(restored);
+        std::fs::remove_dir_all(restore_path.as_str()).unwrap();
+
+        // restoring an explicit earlier 'backup_id' reflects that backup's state instead
+        Storage::restore_from_backup(backup_dir.as_str(), restore_path.as_str(), Some(backup_id1)).unwrap();
+        let restored = Storage::open(restore_path.as_str(), false).unwrap();
+        assert_eq!(restored.get(b"k1").unwrap(), b"v1");
+        drop(restored);
+
+        // purging down to 1 backup drops the oldest one
+        Storage::purge_old_backups(backup_dir.as_str(), 1).unwrap();
+        let infos = Storage::get_backup_info(backup_dir.as_str()).unwrap();
+        assert_eq!(infos.iter().map(|info| info.backup_id).collect::<Vec<_>>(), vec![2]);
+        assert!(Storage::restore_from_backup(backup_dir.as_str(), storage_path.as_str(), Some(backup_id1)).is_err());
+    }
+
+    // Pins down the concurrency failure mode documented on 'create_backup': it's not safe to call from
+    // two racing callers against the same 'backup_dir', because the id a call computes from the manifest
+    // isn't claimed until its checkpoint directory is actually created. Simulates the losing side of that
+    // race by pre-creating the checkpoint directory ('<next id>/CurrentState') 'create_backup' is about to
+    // compute for its next id - standing in
+    // for a concurrent call that already landed its checkpoint there first - and confirms the loser's
+    // 'create_backup' fails outright rather than silently succeeding or retrying with a fresh id.
+    #[test]
+    fn storage_backup_concurrent_create_race_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_backup_concurrent_create_race_tests").unwrap();
+        let (_backup_tmp_dir, backup_dir) = test_dir("storage_backup_concurrent_create_race_tests_backups").unwrap();
+
+        let storage = Storage::open(storage_path.as_str(), true).unwrap();
+
+        let racing_path = crate::common::join_path_strings(backup_dir.as_str(), "1").unwrap();
+        let racing_db_path = crate::common::join_path_strings(racing_path.as_str(), Storage::DB_DIR).unwrap();
+        std::fs::create_dir_all(racing_db_path.as_str()).unwrap();
+
+        assert!(storage.create_backup(backup_dir.as_str()).is_err());
+    }
+}
@@ -1,12 +1,20 @@
 use jni::JNIEnv;
-use jni::objects::{JClass, JString, JObject};
-use jni::sys::{jobject, jboolean, jbyteArray, jobjectArray, jint};
-use crate::common::jni::{unwrap_ptr, create_java_object, exception::_throw_inner, unwrap_mut_ptr};
-use crate::storage::Storage;
+use jni::objects::{JClass, JString, JObject, JValue};
+use jni::sys::{jobject, jboolean, jbyteArray, jobjectArray, jint, jlong};
+use rocksdb::{ColumnFamily, Snapshot};
+use itertools::Itertools;
+use crate::common::Reader;
+use crate::common::jni::{unwrap_ptr, create_java_object, create_storage_java_object, create_transaction_java_object, exception::_throw_inner, unwrap_mut_ptr, java_list_to_vec_byte, rust_vec_to_java};
+use crate::storage::{Storage, StorageLockingOptions, TransactionLockOptions};
+use crate::storage::backup::BackupInfo;
 use crate::storage::transaction::Transaction;
+use crate::storage::optimistic::OptimisticStorage;
+use crate::storage::optimistic_transaction::OptimisticTransaction;
+use crate::common::storage::{ColumnFamiliesManager, DEFAULT_CF_NAME};
 use crate::common::jni::reader;
 use crate::common::jni::transaction_basic;
 use crate::common::jni::cf_manager;
+use crate::common::jni::integer_keyed_cf;
 
 // ------------------------------------- Storage JNI wrappers -------------------------------------
 
@@ -40,6 +48,46 @@ pub extern "system" fn Java_com_horizen_storage_Storage_nativeOpen(
     }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeOpenWithLockingOptions(
+    _env: JNIEnv,
+    _class: JClass,
+    _storage_path: JString,
+    _create_if_missing: jboolean,
+    _max_num_locks: jlong,
+    _transaction_lock_timeout_ms: jlong,
+    _default_lock_timeout_ms: jlong
+) -> jobject
+{
+    let storage_path = _env.get_string(_storage_path)
+        .expect("Should be able to read jstring as Rust String");
+
+    let locking_options = StorageLockingOptions{
+        max_num_locks: _max_num_locks,
+        transaction_lock_timeout_ms: _transaction_lock_timeout_ms,
+        default_lock_timeout_ms: _default_lock_timeout_ms
+    };
+
+    match Storage::open_with_locking_options(
+        storage_path.to_str().unwrap(),
+        _create_if_missing != 0,
+        &locking_options
+    ){
+        Ok(storage) => {
+            let storage_class = _env.find_class("com/horizen/storage/Storage")
+                .expect("Should be able to find class Storage");
+            create_java_object(&_env, &storage_class, storage)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot open storage: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storage_Storage_nativeClose(
     _env: JNIEnv,
@@ -108,6 +156,118 @@ pub extern "system" fn Java_com_horizen_storage_Storage_nativeGetIter(
     )
 }
 
+// Typed counterpart of 'nativeGet' for a column family accessed as an 'IntegerKeyedCf' (see
+// 'common::integer_keyed_cf'): '_key' is a block-height-shaped 'long' instead of a raw byte array.
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeIntegerKeyedGet(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _key: jlong
+) -> jbyteArray
+{
+    integer_keyed_cf::get(
+        unwrap_ptr::<Storage>(&_env, _storage),
+        _env, _cf, _key
+    )
+}
+
+// Typed counterpart of 'nativeGetIter'/'nativeGetIter's range-bounded sibling, scanning an 'IntegerKeyedCf'
+// by '_from'/'_to' block height instead of raw byte bounds. See 'integer_keyed_cf::iter_range' for the
+// forward/backward direction rule.
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeIntegerKeyedIterRange(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _from: jlong,
+    _to: jlong
+) -> jobject
+{
+    integer_keyed_cf::iter_range(
+        unwrap_ptr::<Storage>(&_env, _storage),
+        _env, _cf, _from, _to
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeGetOpt(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _key: jbyteArray,
+    _snapshot: JObject
+) -> jbyteArray
+{
+    reader::get_opt(
+        unwrap_ptr::<Storage>(&_env, _storage),
+        _env, _cf, _key, _snapshot
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeMultiGetOpt(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _keys: jobjectArray,
+    _snapshot: JObject
+) -> jobject
+{
+    reader::multi_get_opt(
+        unwrap_ptr::<Storage>(&_env, _storage),
+        _env, _cf, _keys, _snapshot
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeGetIterOpt(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint,
+    _snapshot: JObject
+) -> jobject
+{
+    reader::get_iter_opt(
+        unwrap_ptr::<Storage>(&_env, _storage),
+        _env, _cf, _mode, _starting_key, _direction, _snapshot
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateSnapshot(
+    _env: JNIEnv,
+    _storage: JObject
+) -> jobject
+{
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    // SAFETY: the returned Snapshot borrows 'storage' for its lifetime; the Java caller must close the
+    // Snapshot (via 'nativeCloseSnapshot') before closing the Storage it was created from - the same
+    // caller-maintained ordering invariant already relied upon for Transaction vs. Storage lifetimes
+    let snapshot: Snapshot<'static> = unsafe {
+        std::mem::transmute(storage.snapshot().expect("Storage should always have an underlying DB reference"))
+    };
+
+    let snapshot_class = _env.find_class("com/horizen/common/Snapshot")
+        .expect("Should be able to find class Snapshot");
+    create_java_object(&_env, &snapshot_class, snapshot)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_common_Snapshot_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _snapshot: *mut Snapshot<'static>,
+){
+    if !_snapshot.is_null(){
+        drop(unsafe { Box::from_raw(_snapshot) })
+    }
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storage_Storage_nativeSetColumnFamily(
     _env: JNIEnv,
@@ -120,6 +280,18 @@ pub extern "system" fn Java_com_horizen_storage_Storage_nativeSetColumnFamily(
     )
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeDeleteColumnFamily(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf_name: JString
+){
+    cf_manager::delete_column_family(
+        unwrap_mut_ptr::<Storage>(&_env, _storage),
+        _env, _cf_name
+    )
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storage_Storage_nativeGetColumnFamily(
     _env: JNIEnv,
@@ -150,6 +322,278 @@ pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateTransaction(
     }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateTransactionWithSnapshot(
+    _env: JNIEnv,
+    _storage: JObject
+) -> jobject
+{
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    if let Ok(transaction) = storage.create_transaction_with_snapshot(){
+        let transaction_class = _env.find_class("com/horizen/storage/Transaction")
+            .expect("Should be able to find class Transaction");
+        create_java_object(&_env, &transaction_class, transaction)
+    } else {
+        JObject::null().into_inner()
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateTransactionWithOptions(
+    _env: JNIEnv,
+    _storage: JObject,
+    _snapshot: jboolean,
+    _lock_timeout_ms: jlong,
+    _deadlock_detect: jboolean,
+    _deadlock_detect_depth: jlong
+) -> jobject
+{
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    let options = TransactionLockOptions{
+        snapshot: _snapshot != 0,
+        lock_timeout_ms: _lock_timeout_ms,
+        deadlock_detect: _deadlock_detect != 0,
+        deadlock_detect_depth: _deadlock_detect_depth
+    };
+
+    if let Ok(transaction) = storage.create_transaction_with_options(&options){
+        let transaction_class = _env.find_class("com/horizen/storage/Transaction")
+            .expect("Should be able to find class Transaction");
+        create_java_object(&_env, &transaction_class, transaction)
+    } else {
+        JObject::null().into_inner()
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateTransactionWithWriteOptions(
+    _env: JNIEnv,
+    _storage: JObject,
+    _sync: jboolean,
+    _disable_wal: jboolean
+) -> jobject
+{
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    if let Ok(transaction) = storage.create_transaction_with_write_options(_sync != 0, _disable_wal != 0){
+        let transaction_class = _env.find_class("com/horizen/storage/Transaction")
+            .expect("Should be able to find class Transaction");
+        create_java_object(&_env, &transaction_class, transaction)
+    } else {
+        JObject::null().into_inner()
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateCheckpoint(
+    _env: JNIEnv,
+    _storage: JObject,
+    _checkpoint_path: JString
+){
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    let checkpoint_path = _env
+        .get_string(_checkpoint_path)
+        .expect("Should be able to read _checkpoint_path jstring as JavaStr");
+
+    match storage.create_checkpoint(checkpoint_path.to_str().expect("Should be able to convert the checkpoint_path to Rust String")) {
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot create a checkpoint: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeCreateBackup(
+    _env: JNIEnv,
+    _storage: JObject,
+    _backup_dir: JString
+) -> jint
+{
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    let backup_dir = _env
+        .get_string(_backup_dir)
+        .expect("Should be able to read _backup_dir jstring as JavaStr");
+
+    match storage.create_backup(backup_dir.to_str().expect("Should be able to convert the backup_dir to Rust String")) {
+        Ok(backup_id) => backup_id as jint,
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot create a backup: {:?}", e).as_str(),
+                0
+            )
+        }
+    }
+}
+
+// Static (not storage-instance-bound) JNI wrappers for 'Storage::restore_from_backup'/'get_backup_info'/
+// 'purge_old_backups', mirroring 'nativeClose' in taking a 'JClass' rather than a 'JObject' receiver -
+// these operate purely on an on-disk 'backup_dir', independent of any open Storage instance.
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeRestoreFromBackup(
+    _env: JNIEnv,
+    _class: JClass,
+    _backup_dir: JString,
+    _restore_path: JString,
+    _backup_id: jint, // a negative value means "restore the most recent backup"
+){
+    let backup_dir = _env
+        .get_string(_backup_dir)
+        .expect("Should be able to read _backup_dir jstring as JavaStr");
+    let restore_path = _env
+        .get_string(_restore_path)
+        .expect("Should be able to read _restore_path jstring as JavaStr");
+    let backup_id = if _backup_id < 0 { None } else { Some(_backup_id as u32) };
+
+    match Storage::restore_from_backup(
+        backup_dir.to_str().expect("Should be able to convert the backup_dir to Rust String"),
+        restore_path.to_str().expect("Should be able to convert the restore_path to Rust String"),
+        backup_id
+    ) {
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot restore from backup: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeGetBackupInfo(
+    _env: JNIEnv,
+    _class: JClass,
+    _backup_dir: JString
+) -> jobjectArray
+{
+    let backup_dir = _env
+        .get_string(_backup_dir)
+        .expect("Should be able to read _backup_dir jstring as JavaStr");
+
+    match Storage::get_backup_info(backup_dir.to_str().expect("Should be able to convert the backup_dir to Rust String")) {
+        Ok(infos) => {
+            rust_vec_to_java(
+                &_env, infos, "com/horizen/storage/BackupInfo",
+                |env, info: BackupInfo| {
+                    let backup_info_class = env.find_class("com/horizen/storage/BackupInfo")
+                        .expect("Should be able to find class BackupInfo");
+                    env.new_object(backup_info_class, "(IJJ)V", &[
+                        JValue::Int(info.backup_id as jint),
+                        JValue::Long(info.timestamp as jlong),
+                        JValue::Long(info.size as jlong)
+                    ])
+                        .expect("Should be able to create BackupInfo Java-object")
+                        .into_inner()
+                },
+                || JObject::null().into_inner(),
+            )
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot get backup info: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativePurgeOldBackups(
+    _env: JNIEnv,
+    _class: JClass,
+    _backup_dir: JString,
+    _num_to_keep: jint
+){
+    let backup_dir = _env
+        .get_string(_backup_dir)
+        .expect("Should be able to read _backup_dir jstring as JavaStr");
+
+    match Storage::purge_old_backups(backup_dir.to_str().expect("Should be able to convert the backup_dir to Rust String"), _num_to_keep as usize) {
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot purge old backups: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Storage_nativeWriteBatch(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cfs_to_put: jobjectArray,     // ColumnFamily[]
+    _keys_to_put: JObject,         // List<byte[]>
+    _values_to_put: JObject,       // List<byte[]>
+    _cfs_to_delete: jobjectArray,  // ColumnFamily[]
+    _keys_to_delete: JObject,      // List<byte[]>
+    _sync: jboolean,
+    _disable_wal: jboolean
+){
+    let storage = unwrap_ptr::<Storage>(&_env, _storage);
+
+    let read_cfs = |array: jobjectArray| -> Vec<&ColumnFamily> {
+        let len = _env.get_array_length(array).expect("Should be able to get the length of a CF array");
+        (0..len).map(|i| {
+            let cf_obj = _env.get_object_array_element(array, i)
+                .expect("Should be able to get a CF array element");
+            unwrap_ptr::<ColumnFamily>(&_env, cf_obj)
+        }).collect()
+    };
+
+    let cfs_to_put = read_cfs(_cfs_to_put);
+    let cfs_to_delete = read_cfs(_cfs_to_delete);
+
+    let keys_to_put = java_list_to_vec_byte(&_env, _keys_to_put)
+        .expect("Should be able to convert Java list of keys to put to a Rust vector");
+    let values_to_put = java_list_to_vec_byte(&_env, _values_to_put)
+        .expect("Should be able to convert Java list of values to put to a Rust vector");
+    let keys_to_delete = java_list_to_vec_byte(&_env, _keys_to_delete)
+        .expect("Should be able to convert Java list of keys to delete to a Rust vector");
+
+    if cfs_to_put.len() != keys_to_put.len() || keys_to_put.len() != values_to_put.len() {
+        throw!(
+            &_env, "java/lang/Exception",
+            "Lists of column families, keys and values to put should be of the same length"
+        )
+    }
+    if cfs_to_delete.len() != keys_to_delete.len() {
+        throw!(
+            &_env, "java/lang/Exception",
+            "Lists of column families and keys to delete should be of the same length"
+        )
+    }
+
+    let to_put = cfs_to_put.iter().zip(keys_to_put.iter().zip(values_to_put.iter()))
+        .map(|(cf, (key, value))| (*cf, key.as_slice(), value.as_slice()))
+        .collect_vec();
+    let to_delete = cfs_to_delete.iter().zip(keys_to_delete.iter())
+        .map(|(cf, key)| (*cf, key.as_slice()))
+        .collect_vec();
+
+    match storage.write_batch(&to_put, &to_delete, _sync != 0, _disable_wal != 0) {
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot apply the write batch: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
 // ------------------------------------- Transaction JNI wrappers -------------------------------------
 
 #[no_mangle]
@@ -237,6 +681,21 @@ pub extern "system" fn Java_com_horizen_storage_Transaction_nativeGetIter(
     )
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_Transaction_nativeGetForUpdate(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _key: jbyteArray,
+    _exclusive: jboolean
+) -> jbyteArray
+{
+    transaction_basic::get_for_update(
+        unwrap_ptr::<Transaction>(&_env, _transaction),
+        _env, _cf, _key, _exclusive
+    )
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storage_Transaction_nativeUpdate(
     _env: JNIEnv,
@@ -283,3 +742,234 @@ pub extern "system" fn Java_com_horizen_storage_Transaction_nativeRollback(
         _env
     )
 }
+
+// ------------------------------------- OptimisticStorage JNI wrappers -------------------------------------
+// Mirrors 'OptimisticStorageVersioned'/'OptimisticTransactionVersioned' in 'storage_versioned/jni.rs': a
+// narrower wrapper surface than 'Storage'/'Transaction' above. 'OptimisticTransaction' gets only 'commit',
+// 'update' and the shared 'Reader' calls here - no savepoint, rollback or 'get_for_update_cf' wrappers are
+// exported for it, although the Rust type itself implements the full 'TransactionBasic' trait.
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeOpen(
+    _env: JNIEnv,
+    _class: JClass,
+    _storage_path: JString,
+    _create_if_missing: jboolean,
+) -> jobject
+{
+    let storage_path = _env.get_string(_storage_path)
+        .expect("Should be able to read _storage_path jstring as JavaStr");
+
+    match OptimisticStorage::open(
+        storage_path.to_str().expect("Should be able to convert the storage_path to Rust String"),
+        _create_if_missing != 0
+    ){
+        Ok(storage) => {
+            let storage_class = _env.find_class("com/horizen/storage/optimistic/OptimisticStorage")
+                .expect("Should be able to find class OptimisticStorage");
+            create_storage_java_object(&_env, &storage_class, storage)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot open the optimistic storage: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _storage: *mut OptimisticStorage,
+){
+    if !_storage.is_null(){
+        drop(unsafe { Box::from_raw(_storage) })
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeCreateTransaction(
+    _env: JNIEnv,
+    _storage: JObject
+) -> jobject
+{
+    let storage = unwrap_ptr::<OptimisticStorage>(&_env, _storage);
+    let default_cf = storage.get_column_family(DEFAULT_CF_NAME)
+        .expect("Should be able to get the default column family");
+
+    match storage.create_transaction() {
+        Ok(transaction) => {
+            let transaction_class = _env.find_class("com/horizen/storage/optimistic/OptimisticTransaction")
+                .expect("Should be able to find class OptimisticTransaction");
+            create_transaction_java_object(&_env, &transaction_class, transaction, default_cf)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot create a transaction: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeGet(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _key: jbyteArray
+) -> jbyteArray
+{
+    reader::get(
+        unwrap_ptr::<OptimisticStorage>(&_env, _storage),
+        _env, _cf, _key
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeMultiGet(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _keys: jobjectArray
+) -> jobject
+{
+    reader::multi_get(
+        unwrap_ptr::<OptimisticStorage>(&_env, _storage),
+        _env, _cf, _keys
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeIsEmpty(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+) -> jboolean
+{
+    reader::is_empty(
+        unwrap_ptr::<OptimisticStorage>(&_env, _storage),
+        _env, _cf
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticStorage_nativeGetIter(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    reader::get_iter(
+        unwrap_ptr::<OptimisticStorage>(&_env, _storage),
+        _env, _cf, _mode, _starting_key, _direction
+    )
+}
+
+// ------------------------------------- OptimisticTransaction JNI wrappers -------------------------------------
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _transaction: *mut OptimisticTransaction,
+){
+    if !_transaction.is_null(){
+        drop(unsafe { Box::from_raw(_transaction) })
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeCommit(
+    _env: JNIEnv,
+    _transaction: JObject,
+) {
+    let transaction = unwrap_ptr::<OptimisticTransaction>(&_env, _transaction);
+    match transaction.commit(){
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot commit the transaction: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeGet(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _key: jbyteArray
+) -> jbyteArray
+{
+    reader::get(
+        unwrap_ptr::<OptimisticTransaction>(&_env, _transaction),
+        _env, _cf, _key,
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeMultiGet(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _keys: jobjectArray
+) -> jobject
+{
+    reader::multi_get(
+        unwrap_ptr::<OptimisticTransaction>(&_env, _transaction),
+        _env, _cf, _keys
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeIsEmpty(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+) -> jboolean
+{
+    reader::is_empty(
+        unwrap_ptr::<OptimisticTransaction>(&_env, _transaction),
+        _env, _cf
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeGetIter(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    reader::get_iter(
+        unwrap_ptr::<OptimisticTransaction>(&_env, _transaction),
+        _env, _cf, _mode, _starting_key, _direction
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storage_optimistic_OptimisticTransaction_nativeUpdate(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _to_update: JObject,      // Map<byte[], byte[]>
+    _to_delete: jobjectArray  // byte[][]
+){
+    transaction_basic::update(
+        unwrap_ptr::<OptimisticTransaction>(&_env, _transaction),
+        _env, _cf, _to_update, _to_delete
+    )
+}
@@ -1,14 +1,48 @@
-use rocksdb::{TransactionDB, Options, Error};
+use rocksdb::{TransactionDB, Options, Error, TransactionDBOptions, WriteOptions, ColumnFamily};
 use crate::common::storage::ColumnFamiliesManager;
 use crate::storage::transaction::Transaction;
-use rocksdb::transactions::ops::{TransactionBegin, OpenCF};
+use rocksdb::transactions::ops::{TransactionBegin, OpenCF, Write, CreateCheckpointObject};
 use std::path::Path;
 use crate::common::{InternalReader, Reader, InternalRef, join_path_strings};
-use crate::TransactionInternal;
+pub use crate::common::transaction::{TransactionLockOptions, TransactionWriteOptions};
+use crate::{TransactionInternal, WriteBatch};
 
 pub mod transaction;
+pub mod optimistic;
+pub mod optimistic_transaction;
+pub mod backup;
 pub mod jni;
 
+// Tunable locking parameters for a Storage's underlying TransactionDB, applied once at 'open' time.
+// All timeouts are in milliseconds; -1 means "block indefinitely" and 0 means "fail immediately" (fast-fail).
+pub struct StorageLockingOptions {
+    // Maximum number of locks the DB will hold at once across all transactions (-1 = unlimited)
+    pub max_num_locks: i64,
+    // How long a transaction waits to acquire a lock already held by another transaction, unless the
+    // transaction sets its own 'TransactionLockOptions::lock_timeout_ms'
+    pub transaction_lock_timeout_ms: i64,
+    // How long a write made directly on the DB, outside any transaction (e.g. 'write_batch'), waits to
+    // acquire a lock already held by a transaction
+    pub default_lock_timeout_ms: i64,
+}
+
+impl Default for StorageLockingOptions {
+    fn default() -> Self {
+        // Matches RocksDB's own TransactionDBOptions defaults
+        StorageLockingOptions{ max_num_locks: -1, transaction_lock_timeout_ms: 1000, default_lock_timeout_ms: 1000 }
+    }
+}
+
+impl StorageLockingOptions {
+    fn to_transaction_db_options(&self) -> TransactionDBOptions {
+        let mut opts = TransactionDBOptions::default();
+        opts.set_max_num_locks(self.max_num_locks);
+        opts.set_transaction_lock_timeout(self.transaction_lock_timeout_ms);
+        opts.set_default_lock_timeout(self.default_lock_timeout_ms);
+        opts
+    }
+}
+
 pub struct Storage{
     db: TransactionDB
 }
@@ -30,8 +64,16 @@ impl Storage {
     const DB_DIR: &'static str = "CurrentState";
 
     // Opens a storage located by a specified path or creates a new one if the directory by a specified path doesn't exist and 'create_if_missing' is true
+    // Uses 'StorageLockingOptions::default()' (1000 ms lock timeouts, no limit on the number of locks); see 'open_with_locking_options' to tune it
     // Returns Result with Storage instance or Err with a describing message if some error occurred
     pub fn open(path: &str, create_if_missing: bool) -> Result<Self, Error> {
+        Self::open_with_locking_options(path, create_if_missing, &StorageLockingOptions::default())
+    }
+
+    // Same as 'open' but allows tuning the underlying TransactionDB's locking behavior
+    // (lock/transaction timeouts, max number of locks) via 'locking_options'
+    // Returns Result with Storage instance or Err with a describing message if some error occurred
+    pub fn open_with_locking_options(path: &str, create_if_missing: bool, locking_options: &StorageLockingOptions) -> Result<Self, Error> {
         // The nested subdirectory 'DB_DIR' is needed for ability to detect if storage is not existing even if a specified by the 'path' directory exists
         let db_path = join_path_strings(path.to_owned().as_str(), Self::DB_DIR)?;
 
@@ -48,13 +90,14 @@ impl Storage {
 
         let mut opts = Options::default();
         opts.create_if_missing(create_if_missing);
+        let txn_db_opts = locking_options.to_transaction_db_options();
 
         Ok(
             Storage{
                 db: if db_path_exists {
-                    TransactionDB::open_cf_all(&opts, db_path)?
+                    TransactionDB::open_cf_all_opts(&opts, &txn_db_opts, db_path)?
                 } else {
-                    TransactionDB::open_cf_default(&opts, db_path)?
+                    TransactionDB::open_cf_default_opts(&opts, &txn_db_opts, db_path)?
                 }
             }
         )
@@ -65,6 +108,78 @@ impl Storage {
     pub fn create_transaction(&self) -> Result<Transaction, Error> {
         Ok(Transaction::new(self.db.transaction_default()?))
     }
+
+    // Creates and returns a Transaction which pins a consistent snapshot of the DB at creation time:
+    // all reads through the Reader interface as well as 'get_for_update_cf' observe that snapshot,
+    // and 'get_for_update_cf'-registered keys are validated against it on commit
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction_with_snapshot(&self) -> Result<Transaction, Error> {
+        self.create_transaction_with_options(&TransactionLockOptions{ snapshot: true, ..TransactionLockOptions::default() })
+    }
+
+    // Creates and returns a Transaction configured with the given 'options' (snapshot pinning, lock
+    // acquisition timeout, deadlock detection). See 'TransactionLockOptions' for the individual knobs.
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction_with_options(&self, options: &TransactionLockOptions) -> Result<Transaction, Error> {
+        Ok(Transaction::new(self.db.transaction(&WriteOptions::default(), &options.to_transaction_options())?))
+    }
+
+    // Creates and returns a Transaction whose 'commit()' uses custom write-durability options instead of
+    // the safe defaults every other 'create_transaction*' constructor uses: 'sync' forces an fsync of the
+    // WAL before 'commit()' returns, and 'disable_wal' skips the WAL entirely (faster, but the commit is
+    // lost on crash before the next flush/checkpoint) - the same trade-off 'write_batch' already exposes
+    // for non-transactional writes. A concrete use case: bulk-loading historical sidechain state with
+    // 'disable_wal: true' for speed, then a final transaction committed with 'sync: true' to make the
+    // whole load durable.
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction_with_write_options(&self, sync: bool, disable_wal: bool) -> Result<Transaction, Error> {
+        let write_options = TransactionWriteOptions{ sync, disable_wal };
+        Ok(Transaction::new(self.db.transaction(&write_options.to_write_options(), &TransactionLockOptions::default().to_transaction_options())?))
+    }
+
+    // Atomically applies the specified insertions ('to_put' triples of CF/Key/Value) and removals
+    // ('to_delete' pairs of CF/Key) in a single RocksDB write batch, without going through a transaction:
+    // no locks are taken and no read-back of the written keys is possible within the batch. Intended for
+    // bulk loads (e.g. genesis import, bulk state sync) where per-key transaction bookkeeping is wasted work.
+    // 'sync' forces an fsync of the WAL before returning; 'disable_wal' skips the WAL entirely (faster, but
+    // the batch is lost on crash before the next flush/checkpoint).
+    // Returns Err with describing message if any error occurred
+    pub fn write_batch(&self,
+                        to_put: &Vec<(&ColumnFamily, &[u8], &[u8])>,
+                        to_delete: &Vec<(&ColumnFamily, &[u8])>,
+                        sync: bool,
+                        disable_wal: bool) -> Result<(), Error> {
+        let mut batch = WriteBatch::default();
+        for &(cf, key, value) in to_put {
+            batch.put_cf(cf, key, value);
+        }
+        for &(cf, key) in to_delete {
+            batch.delete_cf(cf, key);
+        }
+
+        let mut write_opts = WriteOptions::default();
+        write_opts.set_sync(sync);
+        write_opts.set_disable_wal(disable_wal);
+
+        self.db.write_opt(batch, &write_opts)
+    }
+
+    // Creates a consistent, point-in-time copy of this Storage's current state under 'checkpoint_path',
+    // using RocksDB's checkpoint mechanism: SST files are hard-linked rather than copied whenever
+    // 'checkpoint_path' is on the same filesystem as this Storage, making the operation nearly instant
+    // regardless of DB size, and falling back to a full copy otherwise. The DB keeps serving reads and
+    // writes normally while the checkpoint is being created.
+    // The resulting directory is laid out the same way 'open'/'open_with_locking_options' expect, so it
+    // can be reopened directly via 'Storage::open(checkpoint_path, false)' - e.g. to restore a backup or
+    // to seed a new node without replaying the whole chain.
+    // The checkpoint is written to the 'CurrentState' subdirectory of 'checkpoint_path', which must not
+    // already exist - RocksDB's checkpoint API requires its target directory to be absent and will error
+    // out otherwise; 'checkpoint_path' itself may already exist.
+    // Returns Err with a describing message if some error occurred
+    pub fn create_checkpoint(&self, checkpoint_path: &str) -> Result<(), Error> {
+        let db_path = join_path_strings(checkpoint_path, Self::DB_DIR)?;
+        self.db.create_checkpoint_object()?.create_checkpoint(db_path)
+    }
 }
 
 
@@ -74,6 +189,9 @@ mod test {
     use crate::common::transaction::TransactionBasic;
     use crate::common::storage::ColumnFamiliesManager;
     use crate::common::{Reader, test_dir, get_all_cf, get_all};
+    use rocksdb::Direction;
+    use crate::storage::StorageLockingOptions;
+    use crate::common::transaction::TransactionLockOptions;
 
     #[test]
     fn storage_tests(){
@@ -265,6 +383,149 @@ mod test {
         test_reader(&storage);
     }
 
+    #[test]
+    fn storage_iter_prefix_range_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_iter_prefix_range_tests").unwrap();
+
+        let mut storage = Storage::open(storage_path.as_str(), true).unwrap();
+        assert!(storage.set_column_family_with_prefix_extractor("cf1", 3).is_ok());
+        let cf1 = storage.get_column_family("cf1").unwrap();
+
+        let tx = storage.create_transaction().unwrap();
+        tx.update_cf(cf1,
+                     &vec![
+                        ("aaa1".as_ref(), "v1".as_ref()),
+                        ("aaa2".as_ref(), "v2".as_ref()),
+                        ("aab1".as_ref(), "v3".as_ref()),
+                        ("bbb1".as_ref(), "v4".as_ref())],
+                     &vec![]).unwrap();
+        tx.commit().unwrap();
+
+        let mut prefixed = storage.iter_prefix(cf1, b"aaa").unwrap()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect::<Vec<_>>();
+        prefixed.sort();
+        assert_eq!(prefixed, vec![
+            (b"aaa1".to_vec(), b"v1".to_vec()),
+            (b"aaa2".to_vec(), b"v2".to_vec()),
+        ]);
+
+        let mut ranged = storage.iter_range(cf1, b"aaa2", b"bbb1").unwrap()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect::<Vec<_>>();
+        ranged.sort();
+        assert_eq!(ranged, vec![
+            (b"aaa2".to_vec(), b"v2".to_vec()),
+            (b"aab1".to_vec(), b"v3".to_vec()),
+        ]);
+
+        // same range, but pushed down into RocksDB via 'get_range_iter_cf' instead of filtered client-side
+        let mut bounded = storage.get_range_iter_cf(cf1, Some(b"aaa2"), Some(b"bbb1"), Direction::Forward).unwrap()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect::<Vec<_>>();
+        bounded.sort();
+        assert_eq!(bounded, vec![
+            (b"aaa2".to_vec(), b"v2".to_vec()),
+            (b"aab1".to_vec(), b"v3".to_vec()),
+        ]);
+
+        // an unbounded upper side scans through to the end of the column family
+        let unbounded_upper = storage.get_range_iter_cf(cf1, Some(b"bbb1"), None, Direction::Forward).unwrap()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect::<Vec<_>>();
+        assert_eq!(unbounded_upper, vec![(b"bbb1".to_vec(), b"v4".to_vec())]);
+
+        // 'get_prefix_iter_cf' derives its upper bound from the prefix instead of taking one explicitly
+        let mut prefixed = storage.get_prefix_iter_cf(cf1, b"aaa").unwrap()
+            .map(|(k, v)| (k.to_vec(), v.to_vec()))
+            .collect::<Vec<_>>();
+        prefixed.sort();
+        assert_eq!(prefixed, vec![
+            (b"aaa1".to_vec(), b"v1".to_vec()),
+            (b"aaa2".to_vec(), b"v2".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn storage_snapshot_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_snapshot_tests").unwrap();
+        let storage = Storage::open(storage_path.as_str(), true).unwrap();
+        let default_cf = storage.get_column_family("default").unwrap();
+
+        let tx0 = storage.create_transaction().unwrap();
+        tx0.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx0.commit().unwrap();
+
+        // Pinning a point-in-time view of the DB after 'k1' = 'v1' was committed
+        let snapshot = storage.snapshot().unwrap();
+        assert_eq!(storage.get_cf_opt(default_cf, b"k1", Some(&snapshot)).unwrap(), b"v1");
+
+        // A write to the live DB after the snapshot was taken isn't observed through the snapshot
+        let tx1 = storage.create_transaction().unwrap();
+        tx1.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx1.commit().unwrap();
+
+        assert_eq!(storage.get(b"k1").unwrap(), b"v2");
+        assert_eq!(storage.get_cf_opt(default_cf, b"k1", Some(&snapshot)).unwrap(), b"v1");
+
+        // Passing no snapshot reads the live state, same as the plain Reader methods
+        assert_eq!(storage.get_cf_opt(default_cf, b"k1", None).unwrap(), b"v2");
+    }
+
+    #[test]
+    fn storage_write_batch_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_write_batch_tests").unwrap();
+        let mut storage = Storage::open(storage_path.as_str(), true).unwrap();
+        storage.set_column_family("cf1").unwrap();
+
+        let default_cf = storage.get_column_family("default").unwrap();
+        let cf1 = storage.get_column_family("cf1").unwrap();
+
+        storage.write_batch(
+            &vec![
+                (default_cf, b"k1".as_ref(), b"v1".as_ref()),
+                (cf1, b"k11".as_ref(), b"v11".as_ref())
+            ],
+            &vec![],
+            true,
+            false
+        ).unwrap();
+
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+        assert_eq!(storage.get_cf(cf1, b"k11").unwrap(), b"v11");
+
+        // A batch atomically mixes puts and deletes across column families
+        storage.write_batch(
+            &vec![(cf1, b"k12".as_ref(), b"v12".as_ref())],
+            &vec![(default_cf, b"k1".as_ref())],
+            false,
+            true
+        ).unwrap();
+
+        assert!(storage.get(b"k1").is_none());
+        assert_eq!(storage.get_cf(cf1, b"k12").unwrap(), b"v12");
+    }
+
+    #[test]
+    fn storage_cf_delete_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_cf_delete_tests").unwrap();
+
+        let mut storage = Storage::open(storage_path.as_str(), true).unwrap();
+
+        assert!(storage.set_column_family("cf1").is_ok());
+        assert!(storage.get_column_family("cf1").is_some());
+
+        assert!(storage.delete_column_family("cf1").is_ok());
+        assert!(storage.get_column_family("cf1").is_none());
+
+        // Deleting an already-absent column family is not an error
+        assert!(storage.delete_column_family("cf1").is_ok());
+
+        // A dropped column family's name can be reused
+        assert!(storage.set_column_family("cf1").is_ok());
+        assert!(storage.get_column_family("cf1").is_some());
+    }
+
     #[test]
     fn storage_transaction_basic_tests(){
         let (_tmp_dir, storage_path) = test_dir("storage_transaction_basic_tests").unwrap();
@@ -364,4 +625,99 @@ mod test {
         // Transaction can't be rolled back after it was committed
         assert!(tx.rollback().is_err());
     }
+
+    #[test]
+    fn storage_snapshot_transaction_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_snapshot_transaction_tests").unwrap();
+        let storage = Storage::open(storage_path.as_str(), true).unwrap();
+
+        // Committing an initial value for 'k1'
+        let tx0 = storage.create_transaction().unwrap();
+        tx0.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx0.commit().unwrap();
+
+        let default_cf = storage.get_column_family("default").unwrap();
+
+        let snapshot_tx = storage.create_transaction_with_snapshot().unwrap();
+        // get_for_update reads the pinned snapshot's value and registers 'k1' for commit-time conflict validation
+        assert_eq!(snapshot_tx.get_for_update_cf(default_cf, b"k1", true).unwrap().unwrap(), b"v1");
+
+        // No concurrent writer touched 'k1' since the snapshot was taken, so the commit succeeds
+        snapshot_tx.update_cf(default_cf, &vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        assert!(snapshot_tx.commit().is_ok());
+        assert_eq!(storage.get(b"k1").unwrap(), b"v2");
+    }
+
+    #[test]
+    fn storage_transaction_write_options_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_transaction_write_options_tests").unwrap();
+        let storage = Storage::open(storage_path.as_str(), true).unwrap();
+
+        // A WAL-disabled transaction still commits successfully and is visible afterwards; only its
+        // crash-durability guarantee differs, which isn't observable from within a single test run
+        let tx1 = storage.create_transaction_with_write_options(false, true).unwrap();
+        tx1.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx1.commit().unwrap();
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+
+        // A synced transaction behaves the same way functionally
+        let tx2 = storage.create_transaction_with_write_options(true, false).unwrap();
+        tx2.update(&vec![("k2".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx2.commit().unwrap();
+        assert_eq!(storage.get(b"k2").unwrap(), b"v2");
+    }
+
+    #[test]
+    fn storage_locking_options_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_locking_options_tests").unwrap();
+
+        let locking_options = StorageLockingOptions{
+            max_num_locks: 1000,
+            transaction_lock_timeout_ms: 50,
+            default_lock_timeout_ms: 50
+        };
+        let storage = Storage::open_with_locking_options(storage_path.as_str(), true, &locking_options).unwrap();
+
+        // A fast-fail transaction should not block waiting to acquire a lock already held by another transaction
+        let tx1 = storage.create_transaction().unwrap();
+        tx1.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+
+        let tx2 = storage.create_transaction_with_options(&TransactionLockOptions{
+            lock_timeout_ms: 0,
+            ..TransactionLockOptions::default()
+        }).unwrap();
+        assert!(tx2.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).is_err());
+
+        tx1.commit().unwrap();
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+    }
+
+    #[test]
+    fn storage_checkpoint_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_checkpoint_tests").unwrap();
+        let (_checkpoint_tmp_dir, checkpoint_path) = test_dir("storage_checkpoint_tests_checkpoint").unwrap();
+
+        let storage = Storage::open(storage_path.as_str(), true).unwrap();
+        let tx = storage.create_transaction().unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit().unwrap();
+
+        storage.create_checkpoint(checkpoint_path.as_str()).unwrap();
+
+        // the checkpoint can be reopened directly as a Storage and reflects the state at checkpoint time
+        let checkpoint = Storage::open(checkpoint_path.as_str(), false).unwrap();
+        assert_eq!(checkpoint.get(b"k1").unwrap(), b"v1");
+
+        // writes to the original storage after the checkpoint was taken aren't observed through the checkpoint
+        let tx2 = storage.create_transaction().unwrap();
+        tx2.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx2.commit().unwrap();
+
+        assert_eq!(storage.get(b"k1").unwrap(), b"v2");
+        assert_eq!(checkpoint.get(b"k1").unwrap(), b"v1");
+
+        // the checkpoint call requires the target directory to be absent, so taking a second checkpoint
+        // at the same path fails
+        assert!(storage.create_checkpoint(checkpoint_path.as_str()).is_err());
+    }
 }
@@ -0,0 +1,119 @@
+use rocksdb::{OptimisticTransactionDB, Options, Error, TransactionDB};
+use crate::common::storage::ColumnFamiliesManager;
+use crate::storage::optimistic_transaction::OptimisticTransaction;
+use rocksdb::transactions::ops::{OptimisticTransactionBegin, OpenCF};
+use std::path::Path;
+use crate::common::{InternalReader, Reader, InternalRef, join_path_strings};
+use crate::TransactionInternal;
+
+// Storage backed by an OptimisticTransactionDB: no locks are taken while a transaction's updates accumulate
+// (unlike 'Storage', which is backed by a pessimistic TransactionDB), so write-write conflicts are only
+// detected at OptimisticTransaction::commit time. This suits low-contention, read-heavy workloads where
+// the pessimistic locking done by 'Storage' is pure overhead.
+pub struct OptimisticStorage{
+    db: OptimisticTransactionDB
+}
+
+impl InternalRef for OptimisticStorage {
+    fn db_ref(&self) -> Option<&TransactionDB> { None }
+    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { None }
+
+    fn transaction_ref(&self) -> Option<&TransactionInternal> { None }
+    fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal> { None }
+
+    fn optimistic_db_ref(&self) -> Option<&OptimisticTransactionDB> { Some(&self.db) }
+    fn optimistic_db_ref_mut(&mut self) -> Option<&mut OptimisticTransactionDB> { Some(&mut self.db) }
+}
+
+impl InternalReader for OptimisticStorage {}
+impl Reader for OptimisticStorage {}
+impl ColumnFamiliesManager for OptimisticStorage {}
+
+impl OptimisticStorage {
+    // Directory for storing a current state of a storage (DB)
+    const DB_DIR: &'static str = "CurrentState";
+
+    // Opens an optimistic-mode storage located by a specified path or creates a new one if the directory
+    // by a specified path doesn't exist and 'create_if_missing' is true
+    // Returns Result with OptimisticStorage instance or Err with a describing message if some error occurred
+    pub fn open(path: &str, create_if_missing: bool) -> Result<Self, Error> {
+        // The nested subdirectory 'DB_DIR' is needed for ability to detect if storage is not existing even if a specified by the 'path' directory exists
+        let db_path = join_path_strings(path.to_owned().as_str(), Self::DB_DIR)?;
+
+        let db_path_exists = Path::new(db_path.as_str()).exists();
+        if !db_path_exists{
+            if !create_if_missing {
+                return Err(Error::new("No need to create a DB".into()));
+            } else {
+                if std::fs::create_dir_all(&db_path).is_err(){
+                    return Err(Error::new("DB directory can't be created".into()))
+                }
+            }
+        }
+
+        let mut opts = Options::default();
+        opts.create_if_missing(create_if_missing);
+
+        Ok(
+            OptimisticStorage{
+                db: if db_path_exists {
+                    OptimisticTransactionDB::open_cf_all(&opts, db_path)?
+                } else {
+                    OptimisticTransactionDB::open_cf_default(&opts, db_path)?
+                }
+            }
+        )
+    }
+
+    // Creates and returns an OptimisticTransaction
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction(&self) -> Result<OptimisticTransaction, Error> {
+        Ok(OptimisticTransaction::new(self.db.transaction_default()?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::storage::optimistic::OptimisticStorage;
+    use crate::common::transaction::TransactionBasic;
+    use crate::common::{Reader, test_dir};
+
+    #[test]
+    fn optimistic_storage_tests(){
+        let (_tmp_dir, storage_path) = test_dir("optimistic_storage_tests").unwrap();
+
+        assert!(OptimisticStorage::open(storage_path.as_str(), false).is_err());
+
+        // just creating a storage, then reopening it with the further 'OptimisticStorage::open' call
+        drop(OptimisticStorage::open(storage_path.as_str(), true).unwrap());
+
+        let storage = OptimisticStorage::open(storage_path.as_str(), false).unwrap();
+        let tx = storage.create_transaction().unwrap();
+
+        assert!(tx.is_empty());
+
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        assert!(storage.is_empty());
+        tx.commit().unwrap();
+        assert!(!storage.is_empty());
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+    }
+
+    #[test]
+    fn optimistic_storage_conflict_tests(){
+        let (_tmp_dir, storage_path) = test_dir("optimistic_storage_conflict_tests").unwrap();
+        let storage = OptimisticStorage::open(storage_path.as_str(), true).unwrap();
+
+        let tx1 = storage.create_transaction().unwrap();
+        let tx2 = storage.create_transaction().unwrap();
+
+        tx1.update(&vec![("k".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx2.update(&vec![("k".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+
+        // The first committer wins...
+        assert!(tx1.commit().is_ok());
+        // ...and the second commit fails with a write-write conflict, not a silent overwrite
+        let err = tx2.commit().unwrap_err();
+        assert!(super::OptimisticTransaction::is_conflict(&err));
+    }
+}
@@ -0,0 +1,49 @@
+use crate::common::transaction::{TransactionBasic, is_conflict_error};
+use crate::OptimisticTransactionInternal;
+use rocksdb::{Error, TransactionDB, OptimisticTransactionDB};
+use crate::TransactionInternal;
+use crate::common::{InternalReader, Reader, InternalRef};
+
+pub struct OptimisticTransaction {
+    transaction: OptimisticTransactionInternal,
+}
+
+impl InternalRef for OptimisticTransaction {
+    fn db_ref(&self) -> Option<&TransactionDB> { None }
+    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { None }
+
+    fn transaction_ref(&self) -> Option<&TransactionInternal> { None }
+    fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal> { None }
+
+    fn optimistic_db_ref(&self) -> Option<&OptimisticTransactionDB> { None }
+    fn optimistic_db_ref_mut(&mut self) -> Option<&mut OptimisticTransactionDB> { None }
+
+    fn optimistic_transaction_ref(&self) -> Option<&OptimisticTransactionInternal> { Some(&self.transaction) }
+    fn optimistic_transaction_ref_mut(&mut self) -> Option<&mut OptimisticTransactionInternal> { Some(&mut self.transaction) }
+}
+
+impl InternalReader for OptimisticTransaction {}
+impl Reader for OptimisticTransaction {}
+impl TransactionBasic for OptimisticTransaction {}
+
+impl OptimisticTransaction {
+    // Creates new instance of OptimisticTransaction (which is a wrapper for OptimisticTransactionInternal)
+    pub(crate) fn new(transaction: OptimisticTransactionInternal) -> Self {
+        OptimisticTransaction{ transaction }
+    }
+
+    // Commits all OptimisticTransaction's updates into the related OptimisticStorage
+    // Unlike Transaction::commit, no locks were held while this transaction's updates were accumulated,
+    // so a concurrent writer may have touched the same keys in the meantime; RocksDB detects that only
+    // now, at commit time, and this returns Err in that case instead of silently overwriting.
+    // Use 'is_conflict' on the returned Err to tell a write-write conflict apart from any other failure.
+    pub fn commit(&self) -> Result<(), Error> {
+        self.transaction.commit()
+    }
+
+    // Returns true if a given commit() failure was caused by a write-write conflict rather than some other error,
+    // so callers can decide whether to retry the transaction
+    pub fn is_conflict(error: &Error) -> bool {
+        is_conflict_error(error)
+    }
+}
@@ -3,6 +3,11 @@ use crate::TransactionInternal;
 use rocksdb::{Error, TransactionDB};
 use crate::common::{InternalReader, Reader, InternalRef};
 
+// A transaction obtained from 'Storage::create_transaction_with_snapshot'/'create_transaction_with_options'
+// with 'snapshot: true' pins the DB's sequence number at creation time; no extra plumbing is needed here
+// for the 'Reader' impl below to honor that - the wrapped 'rocksdb::Transaction's own 'get'/'get_cf' calls
+// already read from the pinned snapshot whenever one was set, the same way 'get_for_update_cf' validates
+// against it at commit time.
 pub struct Transaction {
     transaction: TransactionInternal,
 }
@@ -0,0 +1,67 @@
+use rocksdb::{ColumnFamily, Error, TransactionDB, WriteOptions};
+use crate::common::Reader;
+use crate::WriteBatch;
+
+// Reserved column family holding the single 'format descriptor' key, so 'StorageVersioned::open' can tell
+// which layout version of this crate wrote the data in 'CurrentState' before trusting the manifest/version
+// set it's about to rebuild. Without this, a future change to the internal version-indexing scheme could
+// silently corrupt or misread a directory written by an incompatible build instead of failing fast.
+pub(crate) const FORMAT_CF_NAME: &str = "__format";
+const FORMAT_DESCRIPTOR_KEY: &[u8] = b"format_descriptor";
+
+// Bumped whenever a change to the manifest/checkpoint/version-indexing layout on disk would make an older
+// or newer reader misinterpret what's already written. 'StorageVersioned::open' refuses to open a
+// directory tagged with any other value.
+pub(crate) const CURRENT_FORMAT_VERSION: u16 = 1;
+
+// Everything recorded about a storage's on-disk layout at creation time and re-checked on every 'open'
+pub(crate) struct FormatDescriptor {
+    pub(crate) format_version: u16,
+    pub(crate) versions_stored: usize,
+}
+
+impl FormatDescriptor {
+    fn serialize(&self) -> Vec<u8> {
+        [self.format_version.to_be_bytes().as_ref(), self.versions_stored.to_be_bytes().as_ref()].concat()
+    }
+
+    fn deserialize(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() != std::mem::size_of::<u16>() + std::mem::size_of::<usize>() {
+            return Err(Error::new("Malformed format descriptor".into()));
+        }
+        let (format_version_bytes, versions_stored_bytes) = bytes.split_at(std::mem::size_of::<u16>());
+        Ok(FormatDescriptor{
+            format_version: u16::from_be_bytes(format_version_bytes.try_into().unwrap()),
+            versions_stored: usize::from_be_bytes(versions_stored_bytes.try_into().unwrap()),
+        })
+    }
+}
+
+// Reads the format descriptor recorded in 'reader's 'FORMAT_CF_NAME' column family.
+// Returns 'None' whenever no descriptor has been recorded yet - for a storage directory freshly created by
+// this same 'open' call, and equally for an existing one that has none, which 'StorageVersioned::open'
+// then tags with the current format via 'write_format_descriptor' rather than rejecting.
+pub(crate) fn read_format_descriptor(reader: &dyn Reader, format_cf: &ColumnFamily) -> Result<Option<FormatDescriptor>, Error> {
+    reader.get_cf(format_cf, FORMAT_DESCRIPTOR_KEY)
+        .map(|bytes| FormatDescriptor::deserialize(&bytes))
+        .transpose()
+}
+
+// Records 'descriptor' as the format descriptor for the data in 'db', via a one-off write batch rather
+// than a 'TransactionVersioned::commit' - this is internal bookkeeping, not a user-visible state
+// transition, so it must not create a version the way every other commit does (see 'Storage::write_batch'
+// for the same bypass-the-transaction-layer rationale applied to bulk loads).
+pub(crate) fn write_format_descriptor(db: &TransactionDB, format_cf: &ColumnFamily, descriptor: &FormatDescriptor) -> Result<(), Error> {
+    let mut batch = WriteBatch::default();
+    batch.put_cf(format_cf, FORMAT_DESCRIPTOR_KEY, descriptor.serialize().as_slice());
+    db.write_opt(batch, &WriteOptions::default())
+}
+
+// Returns true if 'error' was raised by 'StorageVersioned::open' because the on-disk format descriptor
+// didn't match what this build of the crate expects (stale/future format version, or a 'versions_stored'
+// retention setting that doesn't match the one the storage was created with), as opposed to some other
+// I/O or DB failure. Mirrors 'transaction::is_conflict_error': 'rocksdb::Error' has no variants to match
+// on, so distinguishing error kinds is done by tagging the message and sniffing for the tag.
+pub fn is_incompatible_format_error(error: &Error) -> bool {
+    error.to_string().contains("[IncompatibleFormat]")
+}
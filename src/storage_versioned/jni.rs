@@ -1,11 +1,17 @@
 use std::ptr::null_mut;
 use jni::JNIEnv;
 use jni::objects::{JClass, JString, JObject};
-use jni::sys::{jboolean, jbyteArray, jint, jobject, jobjectArray};
-use crate::common::jni::{cf_manager, create_cf_java_object, create_jarray, create_storage_java_object, create_transaction_java_object, create_transaction_versioned_java_object, exception::_throw_inner, reader, transaction_basic, unwrap_mut_ptr, unwrap_ptr};
+use jni::sys::{jboolean, jbyteArray, jint, jlong, jobject, jobjectArray};
+use rocksdb::Snapshot;
+use rocksdb::transactions::ops::GetSnapshot;
+use crate::common::Reader;
+use crate::common::jni::{cf_manager, create_cf_java_object, create_java_object, create_java_object_via_handle, create_storage_java_object, create_transaction_java_object, create_transaction_versioned_java_object, exception::{_throw_inner, JniThrowable}, free_via_handle, reader, rust_vec_to_java, transaction_basic, unwrap_mut_ptr, unwrap_ptr, unwrap_via_handle};
 use crate::common::storage::{ColumnFamiliesManager, DEFAULT_CF_NAME};
 use crate::storage_versioned::StorageVersioned;
 use crate::storage_versioned::transaction_versioned::TransactionVersioned;
+use crate::storage_versioned::version_snapshot::VersionSnapshot;
+use crate::storage_versioned::optimistic::OptimisticStorageVersioned;
+use crate::storage_versioned::optimistic_transaction::OptimisticTransactionVersioned;
 
 // ------------------------------------- StorageVersioned JNI wrappers -------------------------------------
 
@@ -137,24 +143,13 @@ pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_native
     let storage = unwrap_ptr::<StorageVersioned>(&_env, _storage);
     match storage.rollback_versions() {
         Ok(rollback_versions) => {
-            let string_class = _env
-                .find_class("java/lang/String")
-                .expect("Should be able to find String class");
-
-            let default_string = _env.new_string("")
-                .expect("Should be able to convert Rust string to Java String");
-
-            let jstrings = rollback_versions.iter()
-                .map(|version|{
-                    _env.new_string(version)
-                        .expect("Should be able to convert Rust string to Java String").into_inner()
-                }).collect::<Vec<_>>();
-
-            create_jarray(
+            rust_vec_to_java(
                 &_env,
-                string_class,
-                default_string.into_inner(),
-                jstrings
+                rollback_versions,
+                "java/lang/String",
+                |env, version| env.new_string(version)
+                    .expect("Should be able to convert Rust string to Java String").into_inner().into_inner(),
+                || JObject::null().into_inner(),
             )
         }
         Err(e) => {
@@ -193,6 +188,36 @@ pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_native
     }
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeVersionHash(
+    _env: JNIEnv,
+    _storage: JObject,
+    _version_id: JString
+)-> jobject {
+    let storage = unwrap_ptr::<StorageVersioned>(&_env, _storage);
+
+    let version_id: String = _env.get_string(_version_id)
+        .expect("Should be able to convert JString to Rust String").into();
+
+    match storage.version_hash(version_id.as_str()) {
+        Ok(hash_opt) => {
+            if let Some(hash) = hash_opt {
+                _env.new_string(hash)
+                    .expect("Should be able to convert Rust string to Java String").into_inner()
+            } else {
+                JObject::null().into_inner()
+            }
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot get the content hash of the specified version: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeSetColumnFamily(
     _env: JNIEnv,
@@ -205,6 +230,18 @@ pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_native
     )
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeDeleteColumnFamily(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf_name: JString
+){
+    cf_manager::delete_column_family(
+        unwrap_mut_ptr::<StorageVersioned>(&_env, _storage),
+        _env, _cf_name
+    )
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeGetColumnFamily(
     _env: JNIEnv,
@@ -275,6 +312,165 @@ pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_native
     )
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeGetOpt(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _key: jbyteArray,
+    _snapshot: JObject
+) -> jbyteArray
+{
+    reader::get_opt(
+        unwrap_ptr::<StorageVersioned>(&_env, _storage),
+        _env, _cf, _key, _snapshot
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeMultiGetOpt(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _keys: jobjectArray,
+    _snapshot: JObject
+) -> jobject
+{
+    reader::multi_get_opt(
+        unwrap_ptr::<StorageVersioned>(&_env, _storage),
+        _env, _cf, _keys, _snapshot
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeGetIterOpt(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint,
+    _snapshot: JObject
+) -> jobject
+{
+    reader::get_iter_opt(
+        unwrap_ptr::<StorageVersioned>(&_env, _storage),
+        _env, _cf, _mode, _starting_key, _direction, _snapshot
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeGetRangeIter(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _lower: jbyteArray,
+    _upper: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    reader::get_range_iter(
+        unwrap_ptr::<StorageVersioned>(&_env, _storage),
+        _env, _cf, _lower, _upper, _direction
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeIngestExternalFiles(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf_name: JString,
+    _paths: jobjectArray
+){
+    let storage = unwrap_ptr::<StorageVersioned>(&_env, _storage);
+
+    let cf_name = _env.get_string(_cf_name)
+        .expect("Should be able to read _cf_name jstring as JavaStr");
+
+    let paths_len = _env.get_array_length(_paths)
+        .expect("Should be able to get the length of the _paths array");
+    let path_strings: Vec<String> = (0.. paths_len).map(|i| {
+        let path_obj = _env.get_object_array_element(_paths, i)
+            .expect("Should be able to get a _paths array element");
+        _env.get_string(JString::from(path_obj))
+            .expect("Should be able to read a path as JavaStr").into()
+    }).collect();
+    let paths: Vec<&std::path::Path> = path_strings.iter().map(|path| std::path::Path::new(path.as_str())).collect();
+
+    match storage.ingest_external_files(
+        cf_name.to_str().expect("Should be able to convert the cf_name to Rust String"),
+        paths.as_slice()
+    ) {
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot ingest the external files: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeCreateSnapshot(
+    _env: JNIEnv,
+    _storage: JObject,
+    _version_id: JString
+) -> jobject
+{
+    let storage = unwrap_ptr::<StorageVersioned>(&_env, _storage);
+    let version_id = _env.get_string(_version_id)
+        .expect("Should be able to read _version_id jstring as JavaStr");
+
+    match storage.create_snapshot(version_id.to_str().expect("Should be able to convert the version_id to Rust String")) {
+        Ok(snapshot) => {
+            // SAFETY: the returned VersionSnapshot holds its own 'Arc' clone of the version DB it was
+            // created from (see 'StorageVersioned::create_snapshot'), so unlike the plain 'Snapshot' JNI
+            // wrapper in storage/jni.rs, the lifetime erased here isn't relying on the Java caller to close
+            // this object before closing 'storage' - the version DB stays open regardless of what
+            // 'storage.version_cache' does to its own reference, or of 'storage' itself being closed, for
+            // as long as this boxed VersionSnapshot is retained.
+            let snapshot: VersionSnapshot<'static> = unsafe { std::mem::transmute(snapshot) };
+            let snapshot_class = _env.find_class("com/horizen/storageVersioned/VersionSnapshot")
+                .expect("Should be able to find class VersionSnapshot");
+            // Boxed via 'handle_registry' rather than the raw-pointer 'create_java_object' - first real
+            // call site for the validated scheme, see 'common::jni::handle_registry'.
+            create_java_object_via_handle(&_env, &snapshot_class, snapshot)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot create a snapshot of the specified version: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_StorageVersioned_nativeGetSnapshot(
+    _env: JNIEnv,
+    _storage: JObject
+) -> jobject
+{
+    let storage = unwrap_ptr::<StorageVersioned>(&_env, _storage);
+
+    // Unlike the plain 'Snapshot' JNI wrapper in storage/jni.rs (which this used to reuse), the boxed value
+    // here carries its own 'Arc' clone of the CurrentState DB (via 'db_handle') rather than a bare borrow of
+    // 'storage' - so the Java caller closing it before closing the StorageVersioned is no longer load-bearing
+    // for memory safety; instead, 'StorageVersioned::rollback' refuses to run while this clone is outstanding,
+    // the same way historical-version snapshots are protected - see 'StorageVersioned::create_snapshot'.
+    let db = storage.db_handle();
+    let snapshot = unsafe { std::mem::transmute::<Snapshot, Snapshot<'static>>(db.snapshot()) };
+    let snapshot = VersionSnapshot{ snapshot, _db: db };
+
+    let snapshot_class = _env.find_class("com/horizen/storageVersioned/CurrentStateSnapshot")
+        .expect("Should be able to find class CurrentStateSnapshot");
+    // Boxed via 'handle_registry', same as 'nativeCreateSnapshot' above - both Java classes box this same
+    // Rust 'VersionSnapshot' type, and both read their handle back out of the same "handle" field.
+    create_java_object_via_handle(&_env, &snapshot_class, snapshot)
+}
+
 // ------------------------------------- Transaction JNI wrappers -------------------------------------
 
 #[no_mangle]
@@ -399,6 +595,37 @@ pub extern "system" fn Java_com_horizen_storageVersioned_TransactionVersioned_na
     )
 }
 
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_TransactionVersioned_nativeGetRangeIter(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _lower: jbyteArray,
+    _upper: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    reader::get_range_iter(
+        unwrap_ptr::<TransactionVersioned>(&_env, _transaction),
+        _env, _cf, _lower, _upper, _direction
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_TransactionVersioned_nativeGetForUpdate(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _key: jbyteArray,
+    _exclusive: jboolean
+) -> jbyteArray
+{
+    transaction_basic::get_for_update(
+        unwrap_ptr::<TransactionVersioned>(&_env, _transaction),
+        _env, _cf, _key, _exclusive
+    )
+}
+
 #[no_mangle]
 pub extern "system" fn Java_com_horizen_storageVersioned_TransactionVersioned_nativeUpdate(
     _env: JNIEnv,
@@ -445,3 +672,390 @@ pub extern "system" fn Java_com_horizen_storageVersioned_TransactionVersioned_na
         _env
     )
 }
+
+// ------------------------------------- VersionSnapshot JNI wrappers -------------------------------------
+// Boxed/unboxed through 'handle_registry' (see 'common::jni::{create_java_object_via_handle, unwrap_via_handle,
+// free_via_handle}') rather than the raw-pointer 'create_java_object'/'unwrap_ptr' scheme every other
+// wrapper in this file still uses - the first real call site for the validated scheme, chosen because
+// 'VersionSnapshot'/'CurrentStateSnapshot' box the exact same Rust type under two different Java classes,
+// which the raw-pointer scheme's per-'T' 'get_field_name' can't actually tell apart by field name; a shared
+// opaque handle looked up by 'TypeId' doesn't need to.
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_VersionSnapshot_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _handle: jlong,
+){
+    free_via_handle::<VersionSnapshot<'static>>(_handle)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_VersionSnapshot_nativeGet(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+    _key: jbyteArray
+) -> jbyteArray
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), JObject::null().into_inner())
+    };
+    reader::get(snapshot, _env, _cf, _key)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_VersionSnapshot_nativeMultiGet(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+    _keys: jobjectArray
+) -> jobject
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), JObject::null().into_inner())
+    };
+    reader::multi_get(snapshot, _env, _cf, _keys)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_VersionSnapshot_nativeIsEmpty(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+) -> jboolean
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), Default::default())
+    };
+    reader::is_empty(snapshot, _env, _cf)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_VersionSnapshot_nativeGetIter(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), JObject::null().into_inner())
+    };
+    reader::get_iter(snapshot, _env, _cf, _mode, _starting_key, _direction)
+}
+
+// -------------------------------- CurrentStateSnapshot JNI wrappers --------------------------------
+// Backed by the same 'VersionSnapshot' Rust type as above (it's already exactly "a Snapshot plus the Arc
+// that keeps it alive") - boxed under its own Java class rather than VersionSnapshot's, since a
+// CurrentState snapshot is conceptually distinct (over the live head, not a historical version) even
+// though nothing about its safety story differs; see 'nativeGetSnapshot'. Handle-registry-backed exactly
+// like 'VersionSnapshot' above, and for the same reason.
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_CurrentStateSnapshot_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _handle: jlong,
+){
+    free_via_handle::<VersionSnapshot<'static>>(_handle)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_CurrentStateSnapshot_nativeGet(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+    _key: jbyteArray
+) -> jbyteArray
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), JObject::null().into_inner())
+    };
+    reader::get(snapshot, _env, _cf, _key)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_CurrentStateSnapshot_nativeMultiGet(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+    _keys: jobjectArray
+) -> jobject
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), JObject::null().into_inner())
+    };
+    reader::multi_get(snapshot, _env, _cf, _keys)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_CurrentStateSnapshot_nativeIsEmpty(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+) -> jboolean
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), Default::default())
+    };
+    reader::is_empty(snapshot, _env, _cf)
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_CurrentStateSnapshot_nativeGetIter(
+    _env: JNIEnv,
+    _snapshot: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    let snapshot = match unwrap_via_handle::<VersionSnapshot<'static>>(&_env, _snapshot) {
+        Ok(snapshot) => snapshot,
+        Err(e) => throw!(&_env, e.jclass(), e.message().as_str(), JObject::null().into_inner())
+    };
+    reader::get_iter(snapshot, _env, _cf, _mode, _starting_key, _direction)
+}
+
+// ------------------------------------- OptimisticStorageVersioned JNI wrappers -------------------------------------
+// 'OptimisticStorage'/'OptimisticTransaction' (storage/optimistic.rs, storage/optimistic_transaction.rs)
+// had no JNI wrappers to mirror when this section was first written; their own wrappers now live in
+// 'storage/jni.rs' and this section predates/mirrors them. It follows the shape of the
+// 'StorageVersioned'/'TransactionVersioned' wrappers above, narrowed to the reduced Rust-level API
+// 'OptimisticStorageVersioned' actually exposes (see its doc comment for what was deliberately left out
+// of this first step).
+
+// Second real call site for the JNI conversion/macro layer (see 'nativeListVersions' below for the
+// first): the constructor-shaped counterpart 'jni_export_ctor!', boxed via 'create_storage_java_object'
+// (the 'via' form) since 'OptimisticStorageVersioned's Java constructor also needs the default CF pointer,
+// same as 'OptimisticStorageVersioned::open' has always required
+jni_export_ctor! {
+    fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeOpen(
+        storage_path: String, create_if_missing: bool, versions_stored: i32
+    ) -> OptimisticStorageVersioned as "com/horizen/storageVersioned/optimistic/OptimisticStorageVersioned", via create_storage_java_object
+    {
+        if versions_stored < 0 {
+            return Err(rocksdb::Error::new("Number of stored versions can't be negative".into()))
+        }
+        OptimisticStorageVersioned::open(storage_path.as_str(), create_if_missing, versions_stored as usize)
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _storage: *mut OptimisticStorageVersioned,
+){
+    if !_storage.is_null(){
+        drop(unsafe { Box::from_raw(_storage) })
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeCreateTransaction(
+    _env: JNIEnv,
+    _storage: JObject
+) -> jobject
+{
+    let storage = unwrap_ptr::<OptimisticStorageVersioned>(&_env, _storage);
+    let default_cf = storage.get_column_family(DEFAULT_CF_NAME)
+        .expect("Should be able to get the default column family");
+
+    match storage.create_transaction() {
+        Ok(transaction) => {
+            let transaction_class = _env.find_class("com/horizen/storageVersioned/optimistic/OptimisticTransactionVersioned")
+                .expect("Should be able to find class OptimisticTransactionVersioned");
+            create_transaction_java_object(&_env, &transaction_class, transaction, default_cf)
+        }
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot create a transaction: {:?}", e).as_str(),
+                JObject::null().into_inner()
+            )
+        }
+    }
+}
+
+// First real call site for 'jni_export!' (see 'common::jni::convert'): same null-check/conversion/throw
+// behavior as every hand-written wrapper above, but the receiver unwrap, the 'Vec<String>' -> 'jobjectArray'
+// conversion and the panic-free exception throwing are all handled by the macro instead of spelled out here
+jni_export! {
+    fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeListVersions(storage: &OptimisticStorageVersioned) -> Vec<String> {
+        storage.list_versions()
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeGet(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _key: jbyteArray
+) -> jbyteArray
+{
+    reader::get(
+        unwrap_ptr::<OptimisticStorageVersioned>(&_env, _storage),
+        _env, _cf, _key
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeMultiGet(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _keys: jobjectArray
+) -> jobject
+{
+    reader::multi_get(
+        unwrap_ptr::<OptimisticStorageVersioned>(&_env, _storage),
+        _env, _cf, _keys
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeIsEmpty(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+) -> jboolean
+{
+    reader::is_empty(
+        unwrap_ptr::<OptimisticStorageVersioned>(&_env, _storage),
+        _env, _cf
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticStorageVersioned_nativeGetIter(
+    _env: JNIEnv,
+    _storage: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    reader::get_iter(
+        unwrap_ptr::<OptimisticStorageVersioned>(&_env, _storage),
+        _env, _cf, _mode, _starting_key, _direction
+    )
+}
+
+// ------------------------------------- OptimisticTransactionVersioned JNI wrappers -------------------------------------
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeClose(
+    _env: JNIEnv,
+    _class: JClass,
+    _transaction: *mut OptimisticTransactionVersioned,
+){
+    if !_transaction.is_null(){
+        drop(unsafe { Box::from_raw(_transaction) })
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeCommit(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _version_id: JString
+) {
+    let transaction = unwrap_ptr::<OptimisticTransactionVersioned>(&_env, _transaction);
+    let version_id = _env.get_string(_version_id)
+        .expect("Should be able to read _version_id jstring as JavaStr");
+
+    match transaction.commit(version_id.to_str().expect("Should be able to convert the version_id to Rust String")){
+        Ok(()) => {}
+        Err(e) => {
+            throw!(
+                &_env, "java/lang/Exception",
+                format!("Cannot commit the transaction: {:?}", e).as_str()
+            )
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeGet(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _key: jbyteArray
+) -> jbyteArray
+{
+    reader::get(
+        unwrap_ptr::<OptimisticTransactionVersioned>(&_env, _transaction),
+        _env, _cf, _key,
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeMultiGet(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _keys: jobjectArray
+) -> jobject
+{
+    reader::multi_get(
+        unwrap_ptr::<OptimisticTransactionVersioned>(&_env, _transaction),
+        _env, _cf, _keys
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeIsEmpty(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+) -> jboolean
+{
+    reader::is_empty(
+        unwrap_ptr::<OptimisticTransactionVersioned>(&_env, _transaction),
+        _env, _cf
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeGetIter(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _mode: jint,
+    _starting_key: jbyteArray,
+    _direction: jint
+) -> jobject
+{
+    reader::get_iter(
+        unwrap_ptr::<OptimisticTransactionVersioned>(&_env, _transaction),
+        _env, _cf, _mode, _starting_key, _direction
+    )
+}
+
+#[no_mangle]
+pub extern "system" fn Java_com_horizen_storageVersioned_optimistic_OptimisticTransactionVersioned_nativeUpdate(
+    _env: JNIEnv,
+    _transaction: JObject,
+    _cf: JObject,
+    _to_update: JObject,      // Map<byte[], byte[]>
+    _to_delete: jobjectArray  // byte[][]
+){
+    transaction_basic::update(
+        unwrap_ptr::<OptimisticTransactionVersioned>(&_env, _transaction),
+        _env, _cf, _to_update, _to_delete
+    )
+}
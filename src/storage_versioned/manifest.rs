@@ -0,0 +1,242 @@
+use rocksdb::Error;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use crate::common::join_path_strings;
+
+// Name of the append-only manifest log file within a StorageVersioned's base directory
+const MANIFEST_FILE_NAME: &str = "MANIFEST";
+
+// A single, appended-only change to a StorageVersioned's version set: a checkpoint being registered,
+// a version being aliased onto an already-registered checkpoint with identical content (see
+// 'content_index' below), or an existing version being dropped. The manifest log is just a sequence
+// of these edits (in the style of a LevelDB version_set); 'RecoveredVersions' is nothing but a cache
+// rebuilt by folding them in order via 'VersionManifest::replay'.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum VersionEdit {
+    AddVersion{ id: String, number: usize, hash: String, created_at: u64 },
+    AddAlias{ id: String, number: usize, target_id: String, hash: String, created_at: u64 },
+    DeleteVersion{ id: String },
+}
+
+impl VersionEdit {
+    // Serializes an edit as a single manifest log line
+    fn serialize(&self) -> String {
+        match self {
+            VersionEdit::AddVersion{ id, number, hash, created_at } => format!("A\t{}\t{}\t{}\t{}\n", number, created_at, hash, id),
+            VersionEdit::AddAlias{ id, number, target_id, hash, created_at } => format!("L\t{}\t{}\t{}\t{}\t{}\n", number, created_at, hash, target_id, id),
+            VersionEdit::DeleteVersion{ id } => format!("D\t{}\n", id),
+        }
+    }
+
+    // Parses a single manifest log line back into an edit
+    // Returns None for an empty, malformed, or partially-written line (e.g. a crash mid-append)
+    // so that 'replay' can simply skip it instead of failing recovery outright
+    fn deserialize(line: &str) -> Option<Self> {
+        let mut parts = line.splitn(6, '\t');
+        match parts.next()? {
+            "A" => Some(VersionEdit::AddVersion{
+                number: parts.next()?.parse::<usize>().ok()?,
+                created_at: parts.next()?.parse::<u64>().ok()?,
+                hash: parts.next()?.to_owned(),
+                id: parts.next()?.to_owned()
+            }),
+            "L" => Some(VersionEdit::AddAlias{
+                number: parts.next()?.parse::<usize>().ok()?,
+                created_at: parts.next()?.parse::<u64>().ok()?,
+                hash: parts.next()?.to_owned(),
+                target_id: parts.next()?.to_owned(),
+                id: parts.next()?.to_owned()
+            }),
+            "D" => Some(VersionEdit::DeleteVersion{ id: parts.next()?.to_owned() }),
+            _ => None
+        }
+    }
+}
+
+// The version set rebuilt by folding a sequence of 'VersionEdit's, either during 'VersionManifest::replay'
+// on 'open' or incrementally as new edits are appended. A version whose content digest duplicates an
+// already-stored one is an alias: it is user-visible in 'versions' like any other, but shares its
+// checkpoint directory with its 'target_id' rather than owning one of its own.
+#[derive(Debug, Default)]
+pub(crate) struct RecoveredVersions {
+    pub(crate) versions: HashMap<String, usize>,       // every user-visible VersionID -> VersionNumber (owners and aliases alike)
+    pub(crate) aliases: HashMap<String, String>,       // AliasID -> OwnerID of the checkpoint directory it shares
+    pub(crate) content_index: HashMap<String, String>, // content hash -> OwnerID, to detect a no-op state transition
+    pub(crate) hashes: HashMap<String, String>,        // VersionID -> content hash, for 'StorageVersioned::version_hash'
+    pub(crate) owner_numbers: HashMap<String, usize>,  // OwnerID -> VersionNumber its checkpoint directory is named after
+    pub(crate) directory_refs: HashMap<String, usize>, // OwnerID -> number of still-visible versions (itself + aliases) referencing its directory
+    pub(crate) created_at: HashMap<String, u64>,       // VersionID -> unix timestamp (seconds) it was committed at, for 'RetentionPolicy::TimeBased'
+}
+
+impl RecoveredVersions {
+    // Resolves the version ID whose checkpoint directory physically holds 'id's data: itself if 'id'
+    // owns a checkpoint, or the target it's aliased onto otherwise
+    pub(crate) fn owner_of(&self, id: &str) -> String {
+        self.aliases.get(id).cloned().unwrap_or_else(|| id.to_owned())
+    }
+
+    // Folds a single edit into the version set. Used both by 'replay' to rebuild the set from scratch
+    // and by 'StorageVersioned' to keep its in-memory copy in lockstep with what it just appended
+    pub(crate) fn apply(&mut self, edit: &VersionEdit) {
+        match edit {
+            VersionEdit::AddVersion{ id, number, hash, created_at } => {
+                self.versions.insert(id.clone(), *number);
+                self.hashes.insert(id.clone(), hash.clone());
+                self.content_index.insert(hash.clone(), id.clone());
+                self.owner_numbers.insert(id.clone(), *number);
+                self.created_at.insert(id.clone(), *created_at);
+                *self.directory_refs.entry(id.clone()).or_insert(0) += 1;
+            }
+            VersionEdit::AddAlias{ id, number, target_id, hash, created_at } => {
+                self.versions.insert(id.clone(), *number);
+                self.hashes.insert(id.clone(), hash.clone());
+                self.aliases.insert(id.clone(), target_id.clone());
+                self.created_at.insert(id.clone(), *created_at);
+                *self.directory_refs.entry(target_id.clone()).or_insert(0) += 1;
+            }
+            VersionEdit::DeleteVersion{ id } => {
+                self.versions.remove(id);
+                self.hashes.remove(id);
+                self.created_at.remove(id);
+                let owner_id = self.aliases.remove(id).unwrap_or_else(|| id.clone());
+                if let Some(refs) = self.directory_refs.get_mut(&owner_id) {
+                    *refs -= 1;
+                    if *refs == 0 {
+                        self.directory_refs.remove(&owner_id);
+                        self.owner_numbers.remove(&owner_id);
+                        self.content_index.retain(|_, owner| owner != &owner_id);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Append-only log of 'VersionEdit's backing a StorageVersioned's version set. Each edit is flushed
+// and fsync'd before 'append' returns, so a checkpoint is only considered committed once its
+// AddVersion/AddAlias edit is durable on disk - a crash between creating the checkpoint directory and
+// this fsync is handled by reconciliation in 'StorageVersioned::recover' rather than by the manifest itself.
+pub(crate) struct VersionManifest {
+    file: File,
+}
+
+impl VersionManifest {
+    // Opens (creating if absent) the manifest log file at '<base_path>/MANIFEST' for appending
+    pub(crate) fn open(base_path: &str) -> Result<Self, Error> {
+        let manifest_path = join_path_strings(base_path, MANIFEST_FILE_NAME)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(manifest_path.as_str())
+            .map_err(|e| Error::new(format!("Can't open the version manifest: {:?}", e)))?;
+        Ok(VersionManifest{ file })
+    }
+
+    // Appends 'edit' to the manifest, flushing and fsync-ing it before returning so it is durable
+    // even across a crash immediately after this call
+    pub(crate) fn append(&mut self, edit: &VersionEdit) -> Result<(), Error> {
+        self.file.write_all(edit.serialize().as_bytes())
+            .map_err(|e| Error::new(format!("Can't append to the version manifest: {:?}", e)))?;
+        self.file.sync_data()
+            .map_err(|e| Error::new(format!("Can't fsync the version manifest: {:?}", e)))
+    }
+
+    // Replays the manifest log at '<base_path>/MANIFEST' from the start, folding its edits in order,
+    // to reconstruct the version set as of the last durable edit
+    // Returns an empty version set if the manifest doesn't exist yet (a freshly created storage)
+    pub(crate) fn replay(base_path: &str) -> Result<RecoveredVersions, Error> {
+        let manifest_path = join_path_strings(base_path, MANIFEST_FILE_NAME)?;
+        let mut recovered = RecoveredVersions::default();
+
+        if !Path::new(manifest_path.as_str()).exists() {
+            return Ok(recovered);
+        }
+
+        let file = File::open(manifest_path.as_str())
+            .map_err(|e| Error::new(format!("Can't open the version manifest for replay: {:?}", e)))?;
+
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|e| Error::new(format!("Can't read the version manifest: {:?}", e)))?;
+            if line.is_empty() { continue; }
+            if let Some(edit) = VersionEdit::deserialize(line.as_str()) {
+                recovered.apply(&edit);
+            } // skip a malformed/partially-written trailing line rather than failing recovery
+        }
+        Ok(recovered)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::test_dir;
+
+    #[test]
+    fn version_manifest_replay_tests(){
+        let (_tmp_dir, base_path) = test_dir("version_manifest_replay_tests").unwrap();
+
+        // A freshly created storage has no manifest yet
+        assert!(VersionManifest::replay(base_path.as_str()).unwrap().versions.is_empty());
+
+        let mut manifest = VersionManifest::open(base_path.as_str()).unwrap();
+        manifest.append(&VersionEdit::AddVersion{ id: "v1".into(), number: 0, hash: "h1".into(), created_at: 1 }).unwrap();
+        manifest.append(&VersionEdit::AddVersion{ id: "v2".into(), number: 1, hash: "h2".into(), created_at: 2 }).unwrap();
+        manifest.append(&VersionEdit::DeleteVersion{ id: "v1".into() }).unwrap();
+
+        let versions = VersionManifest::replay(base_path.as_str()).unwrap();
+        assert_eq!(versions.versions.len(), 1);
+        assert_eq!(versions.versions["v2"], 1);
+        assert_eq!(versions.created_at["v2"], 2);
+
+        // Reopening and appending more edits is cumulative across 'VersionManifest::open' calls
+        let mut manifest = VersionManifest::open(base_path.as_str()).unwrap();
+        manifest.append(&VersionEdit::AddVersion{ id: "v3".into(), number: 2, hash: "h3".into(), created_at: 3 }).unwrap();
+
+        let versions = VersionManifest::replay(base_path.as_str()).unwrap();
+        assert_eq!(versions.versions.len(), 2);
+        assert_eq!(versions.versions["v2"], 1);
+        assert_eq!(versions.versions["v3"], 2);
+    }
+
+    #[test]
+    fn version_manifest_alias_dedup_tests(){
+        let (_tmp_dir, base_path) = test_dir("version_manifest_alias_dedup_tests").unwrap();
+
+        let mut manifest = VersionManifest::open(base_path.as_str()).unwrap();
+        manifest.append(&VersionEdit::AddVersion{ id: "v1".into(), number: 0, hash: "same".into(), created_at: 1 }).unwrap();
+        manifest.append(&VersionEdit::AddAlias{ id: "v2".into(), number: 1, target_id: "v1".into(), hash: "same".into(), created_at: 2 }).unwrap();
+
+        let versions = VersionManifest::replay(base_path.as_str()).unwrap();
+        assert_eq!(versions.owner_of("v1"), "v1");
+        assert_eq!(versions.owner_of("v2"), "v1");
+        assert_eq!(*versions.directory_refs.get("v1").unwrap(), 2);
+
+        // Deleting the alias drops the reference but keeps the owner's directory alive
+        let mut recovered = versions;
+        recovered.apply(&VersionEdit::DeleteVersion{ id: "v2".into() });
+        assert_eq!(*recovered.directory_refs.get("v1").unwrap(), 1);
+
+        // Deleting the owner too, now that nothing else references it, removes the directory's bookkeeping
+        recovered.apply(&VersionEdit::DeleteVersion{ id: "v1".into() });
+        assert!(!recovered.directory_refs.contains_key("v1"));
+        assert!(!recovered.owner_numbers.contains_key("v1"));
+    }
+
+    #[test]
+    fn version_edit_serialize_roundtrip_tests(){
+        let add = VersionEdit::AddVersion{ id: "some_id".into(), number: 42, hash: "abc".into(), created_at: 1_700_000_000 };
+        assert_eq!(VersionEdit::deserialize(add.serialize().trim_end()).unwrap(), add);
+
+        let alias = VersionEdit::AddAlias{ id: "some_id".into(), number: 42, target_id: "other_id".into(), hash: "abc".into(), created_at: 1_700_000_000 };
+        assert_eq!(VersionEdit::deserialize(alias.serialize().trim_end()).unwrap(), alias);
+
+        let delete = VersionEdit::DeleteVersion{ id: "some_id".into() };
+        assert_eq!(VersionEdit::deserialize(delete.serialize().trim_end()).unwrap(), delete);
+
+        assert!(VersionEdit::deserialize("").is_none());
+        assert!(VersionEdit::deserialize("garbage").is_none());
+        assert!(VersionEdit::deserialize("A\tnot_a_number\t0\thash\tid").is_none());
+    }
+}
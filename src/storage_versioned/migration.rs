@@ -0,0 +1,38 @@
+use rocksdb::{ColumnFamily, Error};
+use crate::common::Reader;
+use crate::common::transaction::TransactionBasic;
+use crate::storage_versioned::transaction_versioned::TransactionVersioned;
+
+// Reserved column family holding the single 'schema version' key, so 'StorageVersioned::open_with_migrations'/'rollback'
+// can tell how many of 'self.migrations' have already been applied to the data currently in 'CurrentState'
+pub(crate) const SCHEMA_CF_NAME: &str = "__schema";
+const SCHEMA_VERSION_KEY: &[u8] = b"schema_version";
+
+// A single schema transform between two adjacent schema versions, applied by
+// 'StorageVersioned::open_with_migrations' (forward, for migrations registered after a version was last
+// committed) and 'StorageVersioned::rollback' (forward again, to bring a version committed under an older
+// schema back up to the current one). Modeled on bottlerocket's AddSettingMigration/RemoveSettingMigration:
+// each migration only needs to know how to add/rewrite the keys a newer schema expects ('forward') and how
+// to strip/revert them back to what the previous schema expected ('backward').
+pub trait Migration {
+    // Rewrites 'tx' so its contents match the schema version this migration transforms data into
+    fn forward(&self, tx: &TransactionVersioned) -> Result<(), Error>;
+    // Reverses 'forward', rewriting 'tx' back to the schema before this migration was applied
+    fn backward(&self, tx: &TransactionVersioned) -> Result<(), Error>;
+}
+
+// Reads the schema version recorded in 'tx's 'SCHEMA_CF_NAME' column family, defaulting to 0 if the
+// reserved key is absent (a storage that predates any migration, or one freshly created)
+pub(crate) fn read_schema_version(tx: &TransactionVersioned, schema_cf: &ColumnFamily) -> Result<usize, Error> {
+    Ok(
+        tx.get_cf(schema_cf, SCHEMA_VERSION_KEY)
+            .and_then(|bytes| bytes.as_slice().try_into().ok())
+            .map(usize::from_be_bytes)
+            .unwrap_or(0)
+    )
+}
+
+// Records 'version' as the schema version of the data currently in 'tx'
+pub(crate) fn write_schema_version(tx: &TransactionVersioned, schema_cf: &ColumnFamily, version: usize) -> Result<(), Error> {
+    tx.update_cf(schema_cf, &vec![(SCHEMA_VERSION_KEY, version.to_be_bytes().as_ref())], &vec![])
+}
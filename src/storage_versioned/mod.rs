@@ -1,32 +1,74 @@
-use rocksdb::{TransactionDB, Error, Options};
+use rocksdb::{TransactionDB, Error, Options, IngestExternalFileOptions, WriteOptions, Snapshot};
 use crate::common::{InternalRef, InternalReader, Reader, clear_path, join_path_strings};
 use crate::TransactionInternal;
 use crate::common::storage::ColumnFamiliesManager;
 use std::path::Path;
-use rocksdb::transactions::ops::{OpenCF, TransactionBegin, CreateCheckpointObject};
+use rocksdb::transactions::ops::{OpenCF, TransactionBegin, CreateCheckpointObject, GetSnapshot, IngestExternalFile};
 use crate::storage_versioned::transaction_versioned::TransactionVersioned;
+use crate::storage_versioned::manifest::{VersionManifest, RecoveredVersions};
+use crate::storage_versioned::version_cache::VersionCache;
+use crate::storage_versioned::version_snapshot::VersionSnapshot;
+use crate::storage_versioned::migration::Migration;
+use crate::storage_versioned::migration;
+use crate::storage_versioned::retention::RetentionPolicy;
+use crate::common::transaction::TransactionLockOptions;
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::sync::{Mutex, Arc};
 use fs_extra::dir::{copy, CopyOptions};
 use std::fs::rename;
-use itertools::{Itertools, Either};
-
+use itertools::{Itertools, Either, EitherOrBoth};
+
+mod manifest;
+pub(crate) mod version_store;
+mod version_cache;
+pub mod version_snapshot;
+pub mod migration;
+pub mod retention;
+pub mod format;
 pub mod transaction_versioned;
+pub mod optimistic;
+pub mod optimistic_transaction;
 pub mod jni;
 
 // Delimiter between version number and version ID in a version (i.e. checkpoint) directory name
 const VERSION_DELIMITER: &str = "__";
+// Maximum number of previous-version DBs kept open at once by 'StorageVersioned::create_snapshot'
+const VERSION_CACHE_CAPACITY: usize = 8;
+
+// A single structural change between two versions of a column family, as produced by 'StorageVersioned::diff_cf'/'diff'
+pub enum VersionDiffEntry {
+    Inserted{ key: Vec<u8>, value: Vec<u8> },
+    Updated{ key: Vec<u8>, old_value: Vec<u8>, new_value: Vec<u8> },
+    Deleted{ key: Vec<u8>, value: Vec<u8> },
+}
+
+pub type VersionDiff = Vec<VersionDiffEntry>;
 
 pub struct StorageVersioned {
-    db: TransactionDB,      // handle of an opened DB which contains current state of a storage
+    db: Arc<TransactionDB>, // handle of an opened DB which contains current state of a storage; reference-counted
+                            // (rather than owned outright) so a Java-held live CurrentState snapshot (see the
+                            // 'nativeGetSnapshot' JNI wrapper) can keep its own clone alive independent of 'self' -
+                            // 'rollback' checks 'Arc::strong_count' before replacing it, refusing to run while any
+                            // such clone is outstanding rather than reopening the DB out from under it
     db_path: String,        // absolute path to the 'CurrentState' directory (which contains the DB with current state)
     versions_path: String,  // absolute path to the 'Versions' directory (which contains storage's versions)
     base_path: String,      // absolute path to the storage (directory which contains the 'CurrentState' and 'Versions' subdirectories)
-    versions_stored: usize  // number of the latest versions of storage to be stored
+    versions_stored: usize, // number of the latest versions of storage to be stored; 0 disables versioning entirely
+    retention_policy: RetentionPolicy, // which versions 'trim_versions' keeps; defaults to 'RetentionPolicy::KeepLatest(versions_stored)' unless 'open_with_retention_policy' is used
+    manifest: Mutex<VersionManifest>, // append-only log of the version set's changes, used to recover it on 'open'
+    version_set: Mutex<RecoveredVersions>, // authoritative in-memory cache of the version set, rebuilt from 'manifest' on 'open'
+    version_cache: Mutex<VersionCache>, // LRU of opened version DBs backing 'create_snapshot'
+    migrations: Vec<Box<dyn Migration>> // registered schema migrations, applied by 'open_with_migrations'/'rollback'; empty unless 'open_with_migrations' is used
 }
 
 impl InternalRef for StorageVersioned {
-    fn db_ref(&self) -> Option<&TransactionDB> { Some(&self.db) }
-    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { Some(&mut self.db) }
+    fn db_ref(&self) -> Option<&TransactionDB> { Some(self.db.as_ref()) }
+    // 'None' whenever a Java-held live CurrentState snapshot still holds its own 'Arc' clone of 'self.db' -
+    // the same outstanding-clone case 'rollback' refuses to run under, see the 'db' field above. Callers
+    // (e.g. 'ColumnFamiliesManager::set_column_family') already surface a 'None' here as an error rather
+    // than panicking, so this doesn't need any handling beyond the type already requiring.
+    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { Arc::get_mut(&mut self.db) }
 
     fn transaction_ref(&self) -> Option<&TransactionInternal> { None }
     fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal> { None }
@@ -38,68 +80,147 @@ impl ColumnFamiliesManager for StorageVersioned {}
 
 impl StorageVersioned {
 
-    // Directory for storing a current state of a storage (DB)
-    const DB_DIR: &'static str = "CurrentState";
-    // Directory for storing versions of the storage (Checkpoints)
-    const VERSIONS_DIR: &'static str = "Versions";
-
     // Opens a storage located by a specified path or creates a new one if the directory by a specified path doesn't exist and 'create_if_missing' is true
     // The 'versions_stored' parameter specifies how many latest versions (0 or more) should be stored for a storage.
     // If at the moment of opening of an existing storage there are more saved versions than 'versions_stored' specifies, then the oldest versions will be removed.
     // Returns Result with StorageVersioned instance or Err with a describing message if some error occurred
     pub fn open(path: &str, create_if_missing: bool, versions_stored: usize) -> Result<Self, Error>{
-        let db_path = join_path_strings(path.to_owned().as_str(), Self::DB_DIR)?;
-
-        // Preparing the CurrentState DB directory if it doesn't exist
-        let db_path_exists = Path::new(db_path.as_str()).exists();
-        if !db_path_exists {
-            if !create_if_missing {
-                return Err(Error::new("No need to create a DB (DB does not exist and the create_if_missing == false)".into()));
-            } else {
-                if std::fs::create_dir_all(&db_path).is_err(){
-                    return Err(Error::new("DB directory can't be created".into()))
-                }
-            }
-        }
+        let paths = version_store::open_paths(path, create_if_missing)?;
 
         let mut opts = Options::default();
         opts.create_if_missing(create_if_missing);
 
         // Opening or creating the CurrentState DB
-        let db =
-            if db_path_exists {
-                TransactionDB::open_cf_all(&opts, &db_path)?
+        let db = Arc::new(
+            if paths.db_path_existed {
+                TransactionDB::open_cf_all(&opts, &paths.db_path)?
             } else {
-                TransactionDB::open_cf_default(&opts, &db_path)?
-            };
-
-        // Creating the Versions directory if it doesn't exist
-        let versions_path = join_path_strings(path.to_owned().as_str(), Self::VERSIONS_DIR)?;
-        if !Path::new(versions_path.as_str()).exists(){
-            if std::fs::create_dir(&versions_path).is_err(){
-                drop(db);
-                return if std::fs::remove_dir(&db_path).is_ok() {
-                    Err(Error::new("Versions directory can't be created".into()))
-                } else {
-                    Err(Error::new("Versions directory can't be created; Can't clean the DB directory".into()))
-                }
+                TransactionDB::open_cf_default(&opts, &paths.db_path)?
             }
-        }
+        );
+
+        let base_path = version_store::absolute_path(path)?;
+        let versions_path = version_store::absolute_path(paths.versions_path.as_str())?;
+
+        let mut manifest = VersionManifest::open(base_path.as_str())?;
+        let version_set = version_store::recover(&mut manifest, base_path.as_str(), versions_path.as_str())?;
 
-        let storage = Self{
+        let mut storage = Self{
             db,
-            db_path: Self::absolute_path(&db_path)?,
-            versions_path: Self::absolute_path(&versions_path)?,
-            base_path: Self::absolute_path(&path.to_owned())?,
-            versions_stored
+            db_path: version_store::absolute_path(paths.db_path.as_str())?,
+            versions_path,
+            base_path,
+            versions_stored,
+            retention_policy: RetentionPolicy::KeepLatest(versions_stored),
+            manifest: Mutex::new(manifest),
+            version_set: Mutex::new(version_set),
+            version_cache: Mutex::new(VersionCache::new(VERSION_CACHE_CAPACITY)),
+            migrations: Vec::new()
         };
-        // Setting the number of recent versions according to the value of 'self.versions_stored'
-        // This method internally also scans all existing versions and checks that their numbers are a consecutive set (by calling 'get_all_versions')
+        // Tags a freshly created storage with the current on-disk format, or validates an existing one
+        // against it, before anything else gets a chance to act on a directory this build can't trust
+        storage.check_format(versions_stored)?;
+        // Setting the number of recent versions according to 'self.retention_policy'
+        storage.trim_versions()?;
+
+        Ok(storage)
+    }
+
+    // Validates (or, for a freshly created storage, records) the on-disk format descriptor: refuses to
+    // open a directory written by an incompatible format version of this crate, or one created with a
+    // different 'versions_stored' retention setting than 'versions_stored' specifies, with a clear
+    // '[IncompatibleFormat]'-tagged error (see 'format::is_incompatible_format_error'). Recording the
+    // descriptor for a fresh storage is a one-off write directly against 'CurrentState' rather than a
+    // transaction commit, so it doesn't itself create a (visible) version.
+    fn check_format(&mut self, versions_stored: usize) -> Result<(), Error> {
+        self.set_column_family(format::FORMAT_CF_NAME)?;
+        let format_cf = self.get_column_family(format::FORMAT_CF_NAME)
+            .ok_or_else(|| Error::new("Missing the reserved format column family right after creating it".into()))?;
+
+        match format::read_format_descriptor(self, format_cf)? {
+            Some(descriptor) => {
+                if descriptor.format_version != format::CURRENT_FORMAT_VERSION {
+                    return Err(Error::new(format!(
+                        "[IncompatibleFormat] Storage was written with format version {} but this build only supports format version {}",
+                        descriptor.format_version, format::CURRENT_FORMAT_VERSION)));
+                }
+                if descriptor.versions_stored != versions_stored {
+                    return Err(Error::new(format!(
+                        "[IncompatibleFormat] Storage was created with versions_stored={} but is being opened with versions_stored={}",
+                        descriptor.versions_stored, versions_stored)));
+                }
+                Ok(())
+            }
+            None => {
+                format::write_format_descriptor(&self.db, format_cf, &format::FormatDescriptor{
+                    format_version: format::CURRENT_FORMAT_VERSION,
+                    versions_stored,
+                })
+            }
+        }
+    }
+
+    // Same as 'open' but lets the caller replace the default sliding-window 'RetentionPolicy::KeepLatest'
+    // with an arbitrary 'retention_policy', e.g. 'RetentionPolicy::Tiered' or 'RetentionPolicy::TimeBased',
+    // consulted by 'trim_versions' every time a new version is committed. 'versions_stored' still governs
+    // whether versioning is enabled at all ('create_version' is a no-op when it's 0) - pass the same value
+    // here that 'retention_policy' is tuned around.
+    // Returns Result with Error if 'open' fails
+    pub fn open_with_retention_policy(path: &str, create_if_missing: bool, versions_stored: usize, retention_policy: RetentionPolicy) -> Result<Self, Error> {
+        let mut storage = Self::open(path, create_if_missing, versions_stored)?;
+        storage.retention_policy = retention_policy;
         storage.trim_versions()?;
+        Ok(storage)
+    }
 
+    // Same as 'open' but additionally registers an ordered list of schema migrations: if the schema
+    // version recorded in 'CurrentState' (see 'storage_versioned::migration') is behind
+    // 'migrations.len()', the pending migrations' 'forward' transforms are applied (in a single
+    // transaction) before this call returns, bringing 'CurrentState' up to date with the code that's
+    // opening it. 'rollback' consults the same 'migrations' list to bring a historical version back up to
+    // the current schema after it's restored.
+    // Returns Result with Error if 'open' fails or applying a pending migration fails
+    pub fn open_with_migrations(path: &str, create_if_missing: bool, versions_stored: usize, migrations: Vec<Box<dyn Migration>>) -> Result<Self, Error> {
+        let mut storage = Self::open(path, create_if_missing, versions_stored)?;
+        storage.migrations = migrations;
+        storage.apply_pending_forward_migrations()?;
         Ok(storage)
     }
 
+    // Applies every migration in 'self.migrations' whose index is at or beyond the schema version
+    // currently recorded for 'CurrentState', in order, inside a single transaction; a no-op if
+    // 'self.migrations' is empty or already fully applied. Used both by 'open_with_migrations' and, after
+    // restoring an older version, by 'rollback'.
+    fn apply_pending_forward_migrations(&mut self) -> Result<(), Error> {
+        if self.migrations.is_empty() {
+            return Ok(())
+        }
+
+        self.set_column_family(migration::SCHEMA_CF_NAME)?;
+        let schema_cf = self.get_column_family(migration::SCHEMA_CF_NAME)
+            .ok_or_else(|| Error::new("Missing the reserved schema column family right after creating it".into()))?;
+
+        let tx = self.create_transaction(None)?;
+        let current_schema_version = migration::read_schema_version(&tx, schema_cf)?;
+
+        if current_schema_version < self.migrations.len() {
+            for schema_migration in &self.migrations[current_schema_version ..] {
+                schema_migration.forward(&tx)?;
+            }
+            migration::write_schema_version(&tx, schema_cf, self.migrations.len())?;
+            tx.commit(format!("__schema_migration_to_{}", self.migrations.len()).as_str())?;
+        }
+        Ok(())
+    }
+
+    // Deletes 'id' from the version set (manifest + in-memory), physically removing its checkpoint
+    // directory only once the last version referencing it (itself or an alias sharing its content) has
+    // been removed; evicts the directory's cached DB handle (if any) first, via 'version_store::delete_version'
+    fn delete_version(&self, version_set: &mut RecoveredVersions, manifest: &mut VersionManifest, id: &str) -> Result<(), Error> {
+        version_store::delete_version(version_set, manifest, self.versions_path.as_str(), id,
+            |owner_id| self.version_cache.lock().unwrap().evict(owner_id))
+    }
+
     // Creates a transaction for a current state of storage if 'version_id_opt' is 'None', or for a specified previous version of the storage otherwise.
     // Returns Result with TransactionVersioned or with Error message if some error occurred
     pub fn create_transaction(&self, version_id_opt: Option<&str>) -> Result<TransactionVersioned, Error> {
@@ -113,63 +234,291 @@ impl StorageVersioned {
         )
     }
 
+    // Creates a transaction over the current state which pins a consistent snapshot of the DB at creation
+    // time: all reads through the Reader interface as well as 'get_for_update_cf' observe that snapshot,
+    // and 'get_for_update_cf'-registered keys are validated against it when the transaction is committed
+    // into a new version via 'TransactionVersioned::commit'.
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction_with_snapshot(&self) -> Result<TransactionVersioned, Error> {
+        self.create_transaction_with_options(&TransactionLockOptions{ snapshot: true, ..TransactionLockOptions::default() })
+    }
+
+    // Creates a transaction over the current state configured with the given 'options' (snapshot pinning,
+    // lock acquisition timeout, deadlock detection). See 'TransactionLockOptions' for the individual knobs.
+    // Unlike 'create_transaction', this always operates on the CurrentState DB rather than a previous
+    // version, since read-conflict validation only makes sense against the state a commit will extend.
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction_with_options(&self, options: &TransactionLockOptions) -> Result<TransactionVersioned, Error> {
+        Ok(TransactionVersioned::new(self.db.transaction(&WriteOptions::default(), &options.to_transaction_options())?, Either::Left(self)))
+    }
+
+    // Bulk-loads pre-built external SST files into a specified column family of the CurrentState DB via
+    // RocksDB's ingest-file path, bypassing the per-key transaction write path used by
+    // 'TransactionVersioned::update'. This is orders of magnitude faster than replaying individual
+    // Put/Delete operations for large initial loads or bulk imports of externally-generated data.
+    // The ingested files are assigned a sequence number above any sequence number already used by the DB
+    // (rather than moved in with their original, possibly-stale ones), so the ingested keys sort
+    // consistently after everything already committed, exactly as if they had been written by a
+    // transaction committed at this point in time.
+    // NOTE: unlike key-level writes through a transaction, ingestion doesn't go through 'GetForUpdate'
+    // locking, so it can't detect a collision with keys an in-flight, uncommitted transaction is about to
+    // write - callers are responsible for not concurrently ingesting into a column family that also has
+    // open writers. Real range-intersection validation against every open transaction's pending writes
+    // would need introspection this fork's 'rocksdb::Transaction' wrapper doesn't expose (pending writes
+    // aren't enumerable from here), so this is left as caller responsibility rather than attempted; see
+    // 'storage_versioned_ingest_external_files_uncommitted_collision_tests' for exactly what happens on
+    // such a collision today (both sides succeed, and it's resolved silently rather than reported).
+    // A successful ingest leaves the ingested data as part of the CurrentState DB like any other write, so
+    // the next 'create_transaction(None).commit(version_id)' checkpoints it into a new version as usual.
+    // Returns Result with Error if the specified column family doesn't exist or the ingestion fails
+    pub fn ingest_external_files(&self, cf_name: &str, paths: &[&Path]) -> Result<(), Error> {
+        let cf = self.get_column_family(cf_name)
+            .ok_or_else(|| Error::new(format!("Column family '{}' doesn't exist", cf_name)))?;
+
+        let mut ingest_opts = IngestExternalFileOptions::default();
+        ingest_opts.set_move_files(false);
+
+        self.db.ingest_external_file_cf_opts(cf, &ingest_opts, paths)
+    }
+
+    // Returns a read-only, point-in-time view of a specified previous version, backed by a RocksDB
+    // snapshot over that version's checkpoint DB. Unlike 'create_transaction(Some(version_id))', the
+    // checkpoint DB is kept open in 'self.version_cache' (an LRU bounded to 'VERSION_CACHE_CAPACITY'
+    // entries) rather than reopened for every call, so repeated snapshot reads of the same version
+    // don't repay the cost of opening it; the returned 'VersionSnapshot' holds its own 'Arc' clone of
+    // that version's 'TransactionDB' (see 'VersionCache'), so it stays open for as long as the
+    // 'VersionSnapshot' is retained even past the cache evicting its own reference - on LRU eviction, on
+    // 'delete_version' removing its last reference, or on 'StorageVersioned' drop.
+    // Returns Result with Error if the specified version doesn't exist or can't be opened
+    pub fn create_snapshot(&self, version_id: &str) -> Result<VersionSnapshot, Error> {
+        let owner_id = {
+            let version_set = self.version_set.lock().unwrap();
+            if !version_set.versions.contains_key(version_id) {
+                return Err(Error::new("Specified version doesn't exist".into()))
+            }
+            version_set.owner_of(version_id)
+        };
+
+        let mut version_cache = self.version_cache.lock().unwrap();
+        let db_version = version_cache.get_or_open(owner_id.as_str(), || self.open_version(version_id))?;
+
+        // SAFETY: 'db_version' is an 'Arc<TransactionDB>' clone that 'VersionSnapshot' will hold onto for
+        // its own lifetime (see its 'db' field), so the TransactionDB this snapshot borrows stays alive
+        // regardless of what 'self.version_cache' does to its own reference afterwards - unlike a plain
+        // borrow of the cache's entry, dropping or evicting the cache's copy no longer frees this one out
+        // from under the snapshot. The transmute only erases the borrow's lifetime to match the 'Arc' it's
+        // paired with; 'db_version.snapshot()' itself still only ever borrows data the 'Arc' keeps alive.
+        let snapshot = unsafe { std::mem::transmute::<Snapshot, Snapshot<'static>>(db_version.snapshot()) };
+        Ok(VersionSnapshot{ snapshot, _db: db_version })
+    }
+
+    // Returns a clone of 'self.db', the CurrentState DB's reference-counted handle. Used by the
+    // 'nativeGetSnapshot' JNI wrapper so a Java-held live snapshot can keep its own strong reference to the
+    // DB it was taken over, the same way 'create_snapshot' above does for historical versions via
+    // 'VersionCache' - see the 'db' field's doc comment and 'rollback'.
+    pub(crate) fn db_handle(&self) -> Arc<TransactionDB> {
+        self.db.clone()
+    }
+
     // Rollbacks current state of the storage to a specified with 'version_id' previous version.
     // All saved versions after the 'version_id' are deleted if rollback is successful.
-    // Returns Result with error message if some error occurs
+    // Returns Result with error message if some error occurs, including if a live CurrentState snapshot
+    // (see 'nativeGetSnapshot') is currently outstanding - see the 'db' field's doc comment
     pub fn rollback(&mut self, version_id: &str) -> Result<(), Error> {
-        let all_versions = self.get_all_versions()?;
-
-        if let Some(&version_number) = all_versions.get(version_id) {
-            // Copying the specified version into the base directory of the Storage
-            if copy(self.compose_version_path(version_id, version_number)?.as_str(),
-                    self.base_path.as_str(),
-                    &CopyOptions::new()).is_ok() {
-
-                // Closing DB in the CurrentState directory
-                // NOTE: is equivalent to drop(replace(&mut self.db, TransactionDB::dummy_db()));
-                self.db = TransactionDB::dummy_db();
-
-                // Removing the CurrentState directory
-                clear_path(self.db_path.as_str())?;
-
-                let version_copy_path =
-                    join_path_strings(self.base_path.as_str(),
-                                     self.compose_version_dir_name(version_id, version_number).as_str())?;
-
-                // Renaming the copied version's directory to the 'CurrentState'
-                if rename(version_copy_path, self.db_path.as_str()).is_ok(){
-                    // Opening the copied DB and putting its handle into 'self.db'
-                    self.db = TransactionDB::open_cf_all(&Options::default(), &self.db_path)?;
-
-                    // Removing all versions which follow the restored version
-                    for (id, &num) in &all_versions {
-                        if num > version_number {
-                            clear_path(
-                                self.compose_version_path(id, num)?.as_str()
-                            )?;
-                        }
-                    }
-                    Ok(())
-                } else {
-                    Err(Error::new("Can't rename the copied version in the base directory".into()))
+        // Refusing to run while a Java-held live snapshot still holds its own 'Arc' clone of 'self.db':
+        // replacing it underneath that clone would silently invalidate the snapshot instead of erroring.
+        // '&mut self' already rules out any in-process (Rust) 'Snapshot' still being borrowed, so the only
+        // way 'self.db' can have more than this one, our own, strong reference is such an outstanding clone.
+        if Arc::strong_count(&self.db) > 1 {
+            return Err(Error::new("Cannot rollback while a live snapshot of the current state is outstanding".into()))
+        }
+
+        let (version_number, owner_id, owner_number, all_versions) = {
+            let version_set = self.version_set.lock().unwrap();
+            let &version_number = version_set.versions.get(version_id)
+                .ok_or_else(|| Error::new("Specified version doesn't exist".into()))?;
+            let owner_id = version_set.owner_of(version_id);
+            let owner_number = *version_set.owner_numbers.get(owner_id.as_str())
+                .ok_or_else(|| Error::new("Missing checkpoint directory metadata for version".into()))?;
+            (version_number, owner_id, owner_number, version_set.versions.clone())
+        };
+
+        // Copying the specified version's checkpoint directory (its owner's, if it's an alias) into the base directory of the Storage
+        if copy(self.compose_version_path(owner_id.as_str(), owner_number)?.as_str(),
+                self.base_path.as_str(),
+                &CopyOptions::new()).is_ok() {
+
+            // Closing DB in the CurrentState directory
+            // NOTE: is equivalent to drop(replace(&mut self.db, Arc::new(TransactionDB::dummy_db())));
+            self.db = Arc::new(TransactionDB::dummy_db());
+
+            // Removing the CurrentState directory
+            clear_path(self.db_path.as_str())?;
+
+            let version_copy_path =
+                join_path_strings(self.base_path.as_str(),
+                                 version_store::compose_version_dir_name(owner_id.as_str(), owner_number).as_str())?;
+
+            // Renaming the copied version's directory to the 'CurrentState'
+            if rename(version_copy_path, self.db_path.as_str()).is_ok(){
+                // Opening the copied DB and putting its handle into 'self.db'
+                self.db = Arc::new(TransactionDB::open_cf_all(&Options::default(), &self.db_path)?);
+
+                // Removing all versions which follow the restored version, recording a DeleteVersion
+                // edit for each so a later 'recover' doesn't resurrect them from the manifest
+                let mut version_set = self.version_set.lock().unwrap();
+                let mut manifest = self.manifest.lock().unwrap();
+                let ids_to_remove: Vec<String> = all_versions.iter()
+                    .filter(|&(_, &num)| num > version_number)
+                    .map(|(id, _)| id.clone())
+                    .collect();
+                for id in ids_to_remove {
+                    self.delete_version(&mut version_set, &mut manifest, id.as_str())?;
                 }
+                drop(version_set);
+                drop(manifest);
+
+                // The restored version may have been committed under an older schema than what
+                // 'self.migrations' currently expects - bring it back up to date the same way 'open_with_migrations'
+                // would, so callers see the same schema regardless of which version is the current head
+                self.apply_pending_forward_migrations()?;
+                Ok(())
             } else {
-                Err(Error::new("Can't copy the specified version into the base directory".into()))
+                Err(Error::new("Can't rename the copied version in the base directory".into()))
             }
         } else {
-            Err(Error::new("Specified version doesn't exist".into()))
+            Err(Error::new("Can't copy the specified version into the base directory".into()))
         }
     }
 
-    // Returns a sorted by creation order list of all existing versions IDs
-    pub fn rollback_versions(&self) -> Result<Vec<String>, Error> {
+    // Promotes a historical version's full key/value state (across every column family currently open
+    // in 'CurrentState') to a brand-new head version named 'new_version_id', without destructively
+    // rewinding history the way 'rollback' does: every version committed in the meantime (including
+    // 'version_id' itself) stays retained unless 'prune_intermediate' is set, in which case every version
+    // strictly newer than 'version_id' (other than the new one just created) is removed exactly as
+    // 'trim_versions' would remove them. This is the forward-moving equivalent of an undo: a real
+    // recovery path that never violates the "one version_id can only be committed once" invariant.
+    // Returns Result with Error if 'version_id' doesn't exist or 'new_version_id' is already in use
+    pub fn rollback_to(&self, version_id: &str, new_version_id: &str, prune_intermediate: bool) -> Result<(), Error> {
+        let historical = self.create_transaction(Some(version_id))?;
+        let cf_names = TransactionDB::list_cf(&Options::default(), self.db_path.as_str())
+            .map_err(|e| Error::new(format!("Can't list the current state's column families: {:?}", e)))?;
+
+        let tx = self.create_transaction(None)?;
+
+        for cf_name in &cf_names {
+            let current_cf = self.get_column_family(cf_name)
+                .ok_or_else(|| Error::new(format!("Column family '{}' doesn't exist in the current state", cf_name)))?;
+            let historical_cf = historical.get_column_family(cf_name)?
+                .ok_or_else(|| Error::new(format!("Column family '{}' doesn't exist in version '{}'", cf_name, version_id)))?;
+
+            let current_values: HashMap<Vec<u8>, Vec<u8>> = tx.get_iter_cf(current_cf)?
+                .map(|kv| (kv.0.to_vec(), kv.1.to_vec())).collect();
+            let historical_values: HashMap<Vec<u8>, Vec<u8>> = historical.get_iter_cf(historical_cf)?
+                .map(|kv| (kv.0.to_vec(), kv.1.to_vec())).collect();
+
+            let to_update: Vec<(Vec<u8>, Vec<u8>)> = historical_values.iter()
+                .filter(|&(k, v)| current_values.get(k) != Some(v))
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect();
+            let to_delete: Vec<Vec<u8>> = current_values.keys()
+                .filter(|k| !historical_values.contains_key(k.as_slice()))
+                .cloned()
+                .collect();
+
+            tx.update_cf(current_cf,
+                         &to_update.iter().map(|(k, v)| (k.as_slice(), v.as_slice())).collect(),
+                         &to_delete.iter().map(|k| k.as_slice()).collect())?;
+        }
+
+        tx.commit(new_version_id)?;
+
+        if prune_intermediate {
+            let mut version_set = self.version_set.lock().unwrap();
+            let &version_number = version_set.versions.get(version_id)
+                .ok_or_else(|| Error::new("Specified version doesn't exist".into()))?;
+
+            let mut manifest = self.manifest.lock().unwrap();
+            let ids_to_remove: Vec<String> = version_set.versions.iter()
+                .filter(|&(id, &num)| num > version_number && id.as_str() != new_version_id)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for id in ids_to_remove {
+                self.delete_version(&mut version_set, &mut manifest, id.as_str())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Computes the structural difference between two versions for a single column family by merge-walking
+    // their (already key-sorted) checkpoint iterators in lockstep, so memory use is O(changed keys) rather
+    // than O(total keys) the way comparing two 'get_all_cf' dumps would be.
+    // A column family present in only one of the two versions is diffed against an empty one.
+    // Returns Result with Error if either version doesn't exist or the column family is absent from both
+    pub fn diff_cf(&self, from_version: &str, to_version: &str, cf_name: &str) -> Result<VersionDiff, Error> {
+        let from_tx = self.create_transaction(Some(from_version))?;
+
+        // Two IDs resolving to the same checkpoint directory (the same version, or an alias of it) can't
+        // differ, and that directory's DB can't be opened a second time while 'from_tx' holds it open
+        if self.version_path(from_version)? == self.version_path(to_version)? {
+            return from_tx.get_column_family(cf_name)?.map(|_| Vec::new())
+                .ok_or_else(|| Error::new(format!("Column family '{}' doesn't exist in version '{}'", cf_name, from_version)));
+        }
+        let to_tx = self.create_transaction(Some(to_version))?;
+
+        let from_cf = from_tx.get_column_family(cf_name)?;
+        let to_cf = to_tx.get_column_family(cf_name)?;
+        if from_cf.is_none() && to_cf.is_none() {
+            return Err(Error::new(format!("Column family '{}' doesn't exist in version '{}' or '{}'", cf_name, from_version, to_version)))
+        }
+
+        let from_iter = from_cf.map(|cf| from_tx.get_iter_cf(cf)).transpose()?.into_iter().flatten();
+        let to_iter = to_cf.map(|cf| to_tx.get_iter_cf(cf)).transpose()?.into_iter().flatten();
+
         Ok(
-            self.get_all_versions()?.into_iter()
-                .sorted_by(|v1, v2| Ord::cmp(&v1.1, &v2.1))
-                .map(|(id, _)|id).collect()
+            from_iter.merge_join_by(to_iter, |(k1, _), (k2, _)| k1.cmp(k2))
+                .filter_map(|entry| match entry {
+                    EitherOrBoth::Left((key, value)) =>
+                        Some(VersionDiffEntry::Deleted{ key: key.to_vec(), value: value.to_vec() }),
+                    EitherOrBoth::Right((key, value)) =>
+                        Some(VersionDiffEntry::Inserted{ key: key.to_vec(), value: value.to_vec() }),
+                    EitherOrBoth::Both((key, old_value), (_, new_value)) if old_value != new_value =>
+                        Some(VersionDiffEntry::Updated{ key: key.to_vec(), old_value: old_value.to_vec(), new_value: new_value.to_vec() }),
+                    EitherOrBoth::Both(..) => None,
+                })
+                .collect()
         )
     }
 
+    // Same as 'diff_cf' but covers every column family present in either 'from_version' or 'to_version',
+    // keyed by CF name. The CF set is read from the two checkpoints themselves (not from 'CurrentState'):
+    // using the live DB's column families here would make 'diff' fail on a CF created since 'to_version'
+    // (present in 'CurrentState' but in neither checkpoint - 'diff_cf' would error on it) and silently
+    // skip a CF dropped since 'to_version' (absent from 'CurrentState' even though both checkpoints still
+    // have it and the drop is exactly the kind of structural change 'diff' should surface).
+    // Returns Result with Error if either version doesn't exist
+    pub fn diff(&self, from_version: &str, to_version: &str) -> Result<HashMap<String, VersionDiff>, Error> {
+        let from_path = self.version_path(from_version)?;
+        let to_path = self.version_path(to_version)?;
+
+        let from_cf_names = TransactionDB::list_cf(&Options::default(), from_path.as_str())
+            .map_err(|e| Error::new(format!("Can't list column families for version '{}': {:?}", from_version, e)))?;
+        let to_cf_names = TransactionDB::list_cf(&Options::default(), to_path.as_str())
+            .map_err(|e| Error::new(format!("Can't list column families for version '{}': {:?}", to_version, e)))?;
+
+        let cf_names: HashSet<String> = from_cf_names.into_iter().chain(to_cf_names.into_iter()).collect();
+
+        cf_names.iter()
+            .map(|cf_name| Ok((cf_name.clone(), self.diff_cf(from_version, to_version, cf_name)?)))
+            .collect()
+    }
+
+    // Returns a sorted by creation order list of all existing versions IDs
+    pub fn rollback_versions(&self) -> Result<Vec<String>, Error> {
+        Ok(version_store::sorted_version_ids(self.get_all_versions()?))
+    }
+
     // Returns the most recent version ID
     pub fn last_version(&self) -> Result<Option<String>, Error> {
         Ok(
@@ -181,179 +530,155 @@ impl StorageVersioned {
         )
     }
 
-    // Converts path into absolute format with Path::canonicalize method
-    fn absolute_path(path: &String) -> Result<String, Error> {
-        if let Ok(path_buf) = Path::new(path.as_str()).canonicalize(){
-            if let Some(path_str) = path_buf.to_str() {
-                Ok(String::from(path_str))
-            } else {
-                Err(Error::new("Can't convert the canonicalized path into string".into()))
-            }
-        } else {
-            Err(Error::new("Path can't be canonicalized".into()))
-        }
+    // Returns the currently retained versions (bounded by whatever 'self.retention_policy' kept) in
+    // commit order, oldest first. An alias of 'rollback_versions' under the name this operation-log
+    // style API is documented with; version numbers already double as the monotonically increasing
+    // sequence the manifest assigns each version, so no separate ordered index needs to be maintained.
+    pub fn list_versions(&self) -> Result<Vec<String>, Error> {
+        self.rollback_versions()
     }
 
-    // Checks if all elements of a given set form a contiguous sequence when being sorted
-    fn is_contiguous_set(set: &Vec<usize>) -> bool {
-        let mut set_sorted = set.clone();
-        set_sorted.sort();
-        let mut prev_elem = 0usize;
+    // Returns the version immediately preceding 'version_id' in commit order - the next-lowest version
+    // number still retained - or 'None' if 'version_id' is the oldest retained version.
+    // Returns Result with Error if 'version_id' doesn't exist
+    pub fn version_parent(&self, version_id: &str) -> Result<Option<String>, Error> {
+        let version_set = self.version_set.lock().unwrap();
+        let &version_number = version_set.versions.get(version_id)
+            .ok_or_else(|| Error::new("Specified version doesn't exist".into()))?;
 
-        for (pos, elem) in set_sorted.into_iter().enumerate() {
-            if pos != 0 &&
-               elem != prev_elem + 1 {
-                return false;
-            }
-            prev_elem = elem;
-        }
-        true
+        Ok(
+            version_set.versions.iter()
+                .filter(|&(_, &num)| num < version_number)
+                .max_by_key(|&(_, &num)| num)
+                .map(|(id, _)| id.clone())
+        )
     }
 
-    // Retrieves a list of all subdirectories from the 'Version' directory,
-    // then creates a HashMap of (VersionID -> VersionNumber) from directories names,
-    // then checks that all VersionNumbers are contiguous
-    // Returns Result with full list of available storage versions as HashMap<VersionID, VersionNumber> or error message if some error occurred
-    fn get_all_versions(&self) -> Result<HashMap<String, usize>, Error> {
-        // Retrieving a list of all subdirectories from the 'Versions' directory
-        let paths = std::fs::read_dir(self.versions_path.as_str()).unwrap();
-        let mut paths_count = 0;
-
-        let id_to_num: HashMap<String, usize> = paths.into_iter()
-            .flat_map(|path|{    // counting the total number of subdirectories with versions
-                paths_count += 1;
-                path
-            })
-            .flat_map(|path|            // extracting versions' directories names from paths
-                path.file_name().into_string()
-            )
-            .flat_map(|num_id|{           // parsing directories names into (version_id, version_number)
-                let num_id_splitted = num_id.as_str().split(VERSION_DELIMITER).collect::<Vec<&str>>();
-                if num_id_splitted.len() != 2 { // directory name should contain only two delimited parts
-                    None
-                } else if let Ok(version_number) = num_id_splitted[0].to_owned().parse::<usize>(){ // parsing the first part as a number
-                    // the second part remains to be a string and is placed as a Key into the HashMap
-                    Some((num_id_splitted[1].to_owned(), version_number)) // (version_id, version_number)
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        // Checking that all directories have been successfully parsed
-        if id_to_num.len() == paths_count {
-            // Checking that all versions numbers are a contiguous sequence
-            if Self::is_contiguous_set(&id_to_num.iter().map(|v|*v.1).collect()) {
-                Ok(id_to_num)
-            } else {
-                Err(Error::new("Versions' numbers are not contiguous".into()))
-            }
-        } else {
-            Err(Error::new("Versions' directories names weren't parsed successfully".into()))
-        }
+    // Returns every currently retained version from newest to oldest, so callers can audit history or
+    // find the most recent valid checkpoint without guessing ids
+    pub fn walk_versions(&self) -> Result<Vec<String>, Error> {
+        let mut versions = self.list_versions()?;
+        versions.reverse();
+        Ok(versions)
     }
 
-    // Returns the next number for a given list of versions' numbers or 0 if the list is empty
-    fn next_version_number(all_versions_numbers: &[usize]) -> Result<usize, Error> {
-        if let Some(&max_version_number) = all_versions_numbers.iter().max() {
-            Ok(max_version_number + 1)
-        } else { // there are no versions (numbers) so start with 0 the numbering
-            Ok(0)
-        }
+    // Returns the authoritative, in-memory version set (VersionID -> VersionNumber) kept by 'self.version_set'.
+    // O(1) besides the clone, and doesn't require version numbers to be a contiguous sequence - it is
+    // simply whatever is durable in the manifest at this point, reconciled for missing directories on
+    // 'open'. This matters for 'RetentionPolicy::Tiered'/'TimeBased', which deliberately leave gaps.
+    fn get_all_versions(&self) -> Result<HashMap<String, usize>, Error> {
+        Ok(self.version_set.lock().unwrap().versions.clone())
     }
 
-    // Composes directory name for a specified version ID and its number as '/versionNumber__versionID'
-    fn compose_version_dir_name(&self, version_id: &str, version_number: usize) -> String {
-        version_number.to_string() + VERSION_DELIMITER + version_id
+    // Returns the content digest recorded for 'version_id' when it was created (see 'create_version'),
+    // or 'None' if no such version exists. Two versions sharing a hash produced a no-op state
+    // transition and were deduped onto the same checkpoint directory via 'VersionEdit::AddAlias'
+    pub fn version_hash(&self, version_id: &str) -> Result<Option<String>, Error> {
+        Ok(self.version_set.lock().unwrap().hashes.get(version_id).cloned())
     }
 
     // Composes absolute path for a specified version as: self.versions_path + '/' + version_dir_name
     fn compose_version_path(&self, version_id: &str, version_number: usize) -> Result<String, Error> {
-        join_path_strings(self.versions_path.as_str(),
-                          self.compose_version_dir_name(version_id, version_number).as_str())
+        version_store::compose_version_path_str(self.versions_path.as_str(), version_id, version_number)
     }
 
-    // Removes the oldest versions (by version number) to make the total number of existing versions the same as 'self.versions_stored'
+    // Removes whichever versions 'self.retention_policy' no longer wants kept, appending a
+    // 'DeleteVersion' edit to the manifest for each one removed; see 'delete_version' for why this
+    // doesn't necessarily clear a directory for every version removed (aliases may share one).
+    // Unlike the original fixed 'KeepLatest' window, 'Tiered'/'TimeBased' policies can deliberately
+    // leave a non-contiguous run of version numbers behind - 'get_all_versions' makes no contiguity
+    // assumption, so nothing else needs to change to support that.
     fn trim_versions(&self) -> Result<(), Error> {
-        let all_versions = self.get_all_versions()?;
-
-        if all_versions.len() > self.versions_stored {
-            if let Some(max_version_number) = all_versions.iter().map(|vn|*vn.1).max(){
-                assert!(max_version_number >= self.versions_stored);
-
-                let min_version_number = max_version_number - self.versions_stored + 1;
-                for (id, &num) in &all_versions {
-                    if num < min_version_number {
-                        clear_path(
-                            self.compose_version_path(id, num)?.as_str()
-                        )?;
-                    }
-                }
-            } else {
-                return Err(Error::new("Can't get the maximum version number".into()))
-            }
-        }
-        Ok(())
+        let mut version_set = self.version_set.lock().unwrap();
+        let mut manifest = self.manifest.lock().unwrap();
+        version_store::trim_versions(&self.retention_policy, &mut version_set, &mut manifest, self.versions_path.as_str(),
+            |owner_id| self.version_cache.lock().unwrap().evict(owner_id))
     }
 
     // Creates a new storage's version (checkpoint of the CurrentState) in the 'Versions' directory.
     // The name of version's directory is composed of a specified 'version_id' and the version's number
     // which is the next after the most recent previous version's number.
+    // The checkpoint is first written to a scratch directory so its content digest can be computed and
+    // compared against 'content_index': if it matches an already-stored version's, this version is
+    // registered as an alias onto that version's checkpoint directory instead of keeping a redundant
+    // copy of identical state. The checkpoint/alias is only considered committed once its manifest edit
+    // has been appended and fsync'd - a crash before that point leaves nothing for 'recover' to pick up.
     // Removes the versions which are older than the most recent 'self.versions_stored' versions.
     // Returns Result with error message if a version with specified ID already exists or some other error occurred
     fn create_version(&self, version_id: &str) -> Result<(), Error> {
         if self.versions_stored > 0 { // no need to create any version in other case
-            let all_versions = self.get_all_versions()?;
-
-            // Checking if the specified 'version_id' already exists among all saved versions
-            if all_versions.get(version_id).is_none() {
-                let all_versions_numbers = all_versions.iter()
-                    .map(|vn|*vn.1).collect::<Vec<usize>>();
-                let next_version_number = Self::next_version_number(
-                    all_versions_numbers.as_slice()
-                )?;
-
-                let version_path_str = self.compose_version_path(version_id, next_version_number)?;
-                let version_path = Path::new(&version_path_str);
-
-                // Creating checkpoint in a directory by 'version_path'
-                self.db.create_checkpoint_object()?.create_checkpoint(version_path)?;
-                // Removing the checkpoints which are not in a sliding window of 'self.versions_stored' size
-                self.trim_versions()
-            } else {
-                Err(Error::new("Specified version already exists".into()))
+            // Rejecting a 'version_id' the manifest's log format couldn't round-trip before paying for a
+            // checkpoint that would just be discarded - see 'version_store::validate_version_id'
+            version_store::validate_version_id(version_id)?;
+
+            // Checking up front (before paying for a checkpoint that would just be discarded) whether
+            // the specified 'version_id' already exists among all saved versions; 'finalize_version'
+            // re-checks this itself right before applying the edit, as the actual source of truth
+            if self.version_set.lock().unwrap().versions.get(version_id).is_some() {
+                return Err(Error::new("Specified version already exists".into()))
             }
+
+            let scratch_path_str = join_path_strings(
+                self.versions_path.as_str(),
+                (".tmp".to_owned() + VERSION_DELIMITER + version_id).as_str()
+            )?;
+            let scratch_path = Path::new(&scratch_path_str);
+
+            // Creating checkpoint in a scratch directory so its content hash can be computed before
+            // deciding whether it duplicates an already-stored version
+            self.db.create_checkpoint_object()?.create_checkpoint(scratch_path)?;
+            let hash = version_store::compute_checkpoint_hash(scratch_path, self.db.latest_sequence_number())?;
+
+            let mut version_set = self.version_set.lock().unwrap();
+            let mut manifest = self.manifest.lock().unwrap();
+            version_store::finalize_version(&mut version_set, &mut manifest, self.versions_path.as_str(), version_id, scratch_path_str.as_str(), hash)?;
+            drop(version_set);
+            drop(manifest);
+
+            // Removing the checkpoints which are not in a sliding window of 'self.versions_stored' size
+            self.trim_versions()
         } else {
             Ok(())
         }
     }
 
+    // Resolves 'version_id' to the on-disk path of the checkpoint directory it's stored in (its own, or -
+    // if 'version_id' is an alias - the directory of the version it aliases).
+    // Returns Result with Error if the specified version doesn't exist
+    fn version_path(&self, version_id: &str) -> Result<String, Error> {
+        version_store::version_path(&self.version_set.lock().unwrap(), self.versions_path.as_str(), version_id)
+    }
+
     // Opens a specified by 'version_id' version (checkpoint) of a storage and returns it's TransactionDB handle.
     // If the specified version doesn't exist or can't be opened then returns an Error with corresponding message
     fn open_version(&self, version_id: &str) -> Result<TransactionDB, Error> {
-        if let Some(version_number) = self.get_all_versions()?.get(version_id){
-            let version_path_str = self.compose_version_path(version_id, *version_number)?;
-            let version_path = Path::new(&version_path_str);
+        let version_path_str = self.version_path(version_id)?;
+        let version_path = Path::new(&version_path_str);
 
-            if version_path.exists(){
-                TransactionDB::open_cf_all(&Options::default(), &version_path)
-            } else {
-                Err(Error::new("Specified version can't be opened".into()))
-            }
+        if version_path.exists(){
+            TransactionDB::open_cf_all(&Options::default(), &version_path)
         } else {
-            Err(Error::new("Specified version doesn't exist".into()))
+            Err(Error::new("Specified version can't be opened".into()))
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::storage_versioned::StorageVersioned;
+    use crate::storage_versioned::{StorageVersioned, VersionDiffEntry};
+    use crate::storage_versioned::migration::Migration;
+    use crate::storage_versioned::retention::RetentionPolicy;
+    use crate::storage_versioned::format;
+    use crate::storage_versioned::transaction_versioned::TransactionVersioned;
     use crate::common::{Reader, test_dir, get_all, get_all_cf};
-    use crate::common::transaction::TransactionBasic;
+    use crate::common::transaction::{TransactionBasic, TransactionLockOptions};
     use crate::common::storage::ColumnFamiliesManager;
     use rand::Rng;
     use itertools::Itertools;
+    use rocksdb::Error;
+    use crate::storage_versioned::VERSION_CACHE_CAPACITY;
+    use crate::common::join_path_strings;
+    use std::path::Path;
 
     // Number of the latest versions of a storage to be stored
     const VERSIONS_STORED: usize = 10;
@@ -364,26 +689,6 @@ mod test {
             .map(|_|rng.gen::<u128>().to_string()).collect()
     }
 
-    #[test]
-    fn storage_versioned_is_contiguous_set_tests(){
-        assert!(StorageVersioned::is_contiguous_set(&vec![]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![0]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![1]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![0, 1]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![5, 6, 7]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![0, 1, 2, 3, 4, 5]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![5, 4, 3, 2, 1, 0]));
-        assert!(StorageVersioned::is_contiguous_set(&vec![4, 2, 1, 0, 5, 3]));
-
-        assert!(!StorageVersioned::is_contiguous_set(&vec![0, 0]));
-        assert!(!StorageVersioned::is_contiguous_set(&vec![1, 1]));
-        assert!(!StorageVersioned::is_contiguous_set(&vec![0, 1, 2, 0]));
-        assert!(!StorageVersioned::is_contiguous_set(&vec![5, 4, 3, 2, 0]));
-        assert!(!StorageVersioned::is_contiguous_set(&vec![0, 2, 3, 4, 5]));
-        assert!(!StorageVersioned::is_contiguous_set(&vec![0, 1, 2, 3, 5]));
-        assert!(!StorageVersioned::is_contiguous_set(&vec![4, 2, 0, 5, 3]));
-    }
-
     #[test]
     fn storage_versioned_versions_trimming_tests(){
         let (_tmp_dir, storage_path) = test_dir("storage_versioned_versions_trimming_tests").unwrap();
@@ -417,6 +722,61 @@ mod test {
         );
     }
 
+    #[test]
+    fn storage_versioned_retention_policy_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_retention_policy_tests").unwrap();
+
+        // 'Tiered' keeps the 3 most recent versions contiguously, plus every 2nd one below that
+        let storage = StorageVersioned::open_with_retention_policy(
+            storage_path.as_str(), true, VERSIONS_STORED,
+            RetentionPolicy::Tiered{ recent: 3, interval: 2 }
+        ).unwrap();
+
+        let versions_ids = gen_versions_ids(10);
+        versions_ids.iter().for_each(
+            |version_id|{
+                assert!(
+                    storage.create_transaction(None).unwrap()
+                        .commit(version_id.as_str()).is_ok()
+                );
+            }
+        );
+
+        // Versions are numbered 0..=9 in commit order; the 3 most recent (7, 8, 9) are always kept,
+        // and below that only the even-numbered ones (0, 2, 4, 6) survive - the odd ones (1, 3, 5) are trimmed
+        for (number, version_id) in versions_ids.iter().enumerate() {
+            let should_exist = number >= 7 || number % 2 == 0;
+            assert_eq!(storage.create_transaction(Some(version_id)).is_ok(), should_exist);
+        }
+    }
+
+    #[test]
+    fn storage_versioned_version_history_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_version_history_tests").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+        assert!(storage.list_versions().unwrap().is_empty());
+        assert!(storage.walk_versions().unwrap().is_empty());
+
+        let versions_ids = gen_versions_ids(3);
+        versions_ids.iter().for_each(
+            |version_id|{
+                assert!(
+                    storage.create_transaction(None).unwrap()
+                        .commit(version_id.as_str()).is_ok()
+                );
+            }
+        );
+
+        assert_eq!(storage.list_versions().unwrap(), versions_ids);
+        assert_eq!(storage.walk_versions().unwrap(), versions_ids.iter().rev().cloned().collect::<Vec<_>>());
+
+        assert!(storage.version_parent("no_such_version").is_err());
+        assert!(storage.version_parent(versions_ids[0].as_str()).unwrap().is_none());
+        assert_eq!(storage.version_parent(versions_ids[1].as_str()).unwrap().unwrap(), versions_ids[0]);
+        assert_eq!(storage.version_parent(versions_ids[2].as_str()).unwrap().unwrap(), versions_ids[1]);
+    }
+
     #[test]
     fn storage_versioned_rollback_tests(){
         assert_ne!(VERSIONS_STORED, 0, "Rollback test can't be run without any versions of a storage");
@@ -493,6 +853,137 @@ mod test {
                    [&versions_content[..= versions_content.len() - 1 - (VERSIONS_STORED - 1)], &[last_kv]].concat())
     }
 
+    // Regression test for 'rollback' refusing to run while a live CurrentState snapshot (as returned by
+    // the 'nativeGetSnapshot' JNI wrapper, simulated here via 'db_handle') still holds its own reference to
+    // 'self.db' - rather than silently reopening the DB out from under it
+    #[test]
+    fn storage_versioned_rollback_refuses_while_current_state_snapshot_outstanding_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_rollback_refuses_while_current_state_snapshot_outstanding_tests").unwrap();
+
+        let mut storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id2").unwrap();
+
+        // Holding an extra 'Arc' clone of 'self.db', exactly as the JNI 'nativeGetSnapshot' wrapper would
+        // for a Java-held live snapshot
+        let live_db_handle = storage.db_handle();
+        assert!(storage.rollback("version_id1").is_err());
+
+        // Once the outstanding clone is dropped, rollback proceeds normally
+        drop(live_db_handle);
+        assert!(storage.rollback("version_id1").is_ok());
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+    }
+
+    #[test]
+    fn storage_versioned_rollback_to_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_rollback_to_tests").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref()), ("k2".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        let tx2 = storage.create_transaction(None).unwrap();
+        tx2.update(&vec![("k2".as_ref(), "v2_updated".as_ref()), ("k3".as_ref(), "v3".as_ref())], &vec!["k1".as_ref()]).unwrap();
+        tx2.commit("version_id2").unwrap();
+
+        assert_eq!(storage.get(b"k1"), None);
+        assert_eq!(storage.get(b"k2").unwrap(), b"v2_updated");
+        assert_eq!(storage.get(b"k3").unwrap(), b"v3");
+
+        // Promoting 'version_id1' to a new head doesn't remove 'version_id2' unless asked to
+        assert!(storage.rollback_to("version_id1", "version_id3", false).is_ok());
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+        assert_eq!(storage.get(b"k2").unwrap(), b"v2");
+        assert_eq!(storage.get(b"k3"), None);
+        assert_eq!(storage.rollback_versions().unwrap(), vec!["version_id1".to_owned(), "version_id2".to_owned(), "version_id3".to_owned()]);
+
+        // Can't reuse a version_id that already exists, whether as a historical version or as the new head
+        assert!(storage.rollback_to("version_id1", "version_id2", false).is_err());
+        assert!(storage.rollback_to("no_such_version", "version_id4", false).is_err());
+
+        // Promoting again, this time pruning every version strictly newer than the one restored
+        let tx4 = storage.create_transaction(None).unwrap();
+        tx4.update(&vec![("k4".as_ref(), "v4".as_ref())], &vec![]).unwrap();
+        tx4.commit("version_id4").unwrap();
+
+        assert!(storage.rollback_to("version_id1", "version_id5", true).is_ok());
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+        assert_eq!(storage.get(b"k4"), None);
+        assert_eq!(storage.rollback_versions().unwrap(), vec!["version_id1".to_owned(), "version_id5".to_owned()]);
+    }
+
+    #[test]
+    fn storage_versioned_diff_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_diff_tests").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref()), ("k2".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        let tx2 = storage.create_transaction(None).unwrap();
+        tx2.update(&vec![("k2".as_ref(), "v2_updated".as_ref()), ("k3".as_ref(), "v3".as_ref())], &vec!["k1".as_ref()]).unwrap();
+        tx2.commit("version_id2").unwrap();
+
+        let diff = storage.diff_cf("version_id1", "version_id2", "default").unwrap();
+        assert_eq!(diff.len(), 3);
+        assert!(diff.iter().any(|entry| matches!(entry,
+            VersionDiffEntry::Deleted{ key, value } if key == b"k1" && value == b"v1")));
+        assert!(diff.iter().any(|entry| matches!(entry,
+            VersionDiffEntry::Updated{ key, old_value, new_value } if key == b"k2" && old_value == b"v2" && new_value == b"v2_updated")));
+        assert!(diff.iter().any(|entry| matches!(entry,
+            VersionDiffEntry::Inserted{ key, value } if key == b"k3" && value == b"v3")));
+
+        // Diffing a version against itself yields no changes
+        assert!(storage.diff_cf("version_id1", "version_id1", "default").unwrap().is_empty());
+
+        // 'diff' covers every column family, keyed by name
+        let full_diff = storage.diff("version_id1", "version_id2").unwrap();
+        assert_eq!(full_diff.get("default").unwrap().len(), 3);
+
+        assert!(storage.diff_cf("no_such_version", "version_id2", "default").is_err());
+        assert!(storage.diff_cf("version_id1", "version_id2", "no_such_cf").is_err());
+    }
+
+    #[test]
+    fn storage_versioned_diff_cf_set_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_diff_cf_set_tests").unwrap();
+
+        let mut storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        storage.set_column_family("extra").unwrap();
+        let tx2 = storage.create_transaction(None).unwrap();
+        tx2.update_cf(tx2.get_column_family("extra").unwrap().unwrap(),
+                      &vec![("k2".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx2.commit("version_id2").unwrap();
+
+        // a CF created after 'version_id2' (only present in 'CurrentState') must not make 'diff' fail
+        storage.set_column_family("created_later").unwrap();
+        let full_diff = storage.diff("version_id1", "version_id2").unwrap();
+        assert!(!full_diff.contains_key("created_later"));
+        assert_eq!(full_diff.get("extra").unwrap().len(), 1);
+
+        // a CF dropped from 'CurrentState' after 'version_id2' must still be reported, since both
+        // checkpoints being diffed still have it
+        storage.delete_column_family("extra").unwrap();
+        let full_diff_after_drop = storage.diff("version_id1", "version_id2").unwrap();
+        assert_eq!(full_diff_after_drop.get("extra").unwrap().len(), 1);
+    }
+
     #[test]
     fn storage_versioned_versioning_tests(){
         let (_tmp_dir, storage_path) = test_dir("storage_versioned_versioning_test").unwrap();
@@ -626,6 +1117,183 @@ mod test {
         test_reader(&storage);
     }
 
+    // Adds a fixed (key, value) marker pair, used to verify a registered migration's 'forward' ran
+    struct AddMarkerMigration {
+        key: &'static [u8],
+        value: &'static [u8],
+    }
+
+    impl Migration for AddMarkerMigration {
+        fn forward(&self, tx: &TransactionVersioned) -> Result<(), Error> {
+            tx.update(&vec![(self.key, self.value)], &vec![])
+        }
+        fn backward(&self, tx: &TransactionVersioned) -> Result<(), Error> {
+            tx.update(&vec![], &vec![self.key])
+        }
+    }
+
+    #[test]
+    fn storage_versioned_migrations_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_migrations_tests").unwrap();
+
+        // Committing a version before any migration is registered
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![(b"k0".as_ref(), b"v0".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id0").unwrap();
+        drop(storage);
+
+        let migrations: Vec<Box<dyn Migration>> = vec![
+            Box::new(AddMarkerMigration{ key: b"migrated_to_v1", value: b"1" })
+        ];
+
+        // Re-opening with a pending migration applies it immediately, on top of the pre-existing data
+        let mut storage = StorageVersioned::open_with_migrations(storage_path.as_str(), true, VERSIONS_STORED, migrations).unwrap();
+        assert_eq!(storage.get(b"k0").unwrap(), b"v0");
+        assert_eq!(storage.get(b"migrated_to_v1").unwrap(), b"1");
+
+        // Re-opening again with the same (already applied) migration is a no-op, not a re-commit
+        let versions_before = storage.rollback_versions().unwrap();
+        drop(storage);
+        let migrations: Vec<Box<dyn Migration>> = vec![
+            Box::new(AddMarkerMigration{ key: b"migrated_to_v1", value: b"1" })
+        ];
+        let mut storage = StorageVersioned::open_with_migrations(storage_path.as_str(), true, VERSIONS_STORED, migrations).unwrap();
+        assert_eq!(storage.rollback_versions().unwrap(), versions_before);
+
+        // Rolling back to the version committed before the migration was registered should bring the
+        // restored data back up to the current schema (the marker key reappears)
+        assert!(storage.rollback("version_id0").is_ok());
+        assert_eq!(storage.get(b"k0").unwrap(), b"v0");
+        assert_eq!(storage.get(b"migrated_to_v1").unwrap(), b"1");
+    }
+
+    #[test]
+    fn storage_versioned_ingest_external_files_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_ingest_external_files_tests").unwrap();
+        let (_sst_tmp_dir, sst_dir) = test_dir("storage_versioned_ingest_external_files_sst").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        // Ingesting into a non-existing column family should fail
+        let sst_path = join_path_strings(sst_dir.as_str(), "ingest.sst").unwrap();
+        assert!(storage.ingest_external_files("no_such_cf", &[Path::new(sst_path.as_str())]).is_err());
+
+        let cf = storage.get_column_family("default").unwrap();
+        let mut writer = rocksdb::SstFileWriter::create(&rocksdb::Options::default());
+        writer.open(sst_path.as_str()).unwrap();
+        writer.put(b"k1", b"v1").unwrap();
+        writer.put(b"k2", b"v2").unwrap();
+        writer.finish().unwrap();
+
+        assert!(storage.is_empty());
+        assert!(storage.ingest_external_files("default", &[Path::new(sst_path.as_str())]).is_ok());
+
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+        assert_eq!(storage.get(b"k2").unwrap(), b"v2");
+        let _ = cf; // only used to assert the column family exists before ingesting into it
+
+        // Ingested data participates in the normal versioning flow
+        let tx = storage.create_transaction(None).unwrap();
+        assert!(tx.commit("version_id1").is_ok());
+        let tx_version = storage.create_transaction(Some("version_id1")).unwrap();
+        assert_eq!(tx_version.get(b"k1").unwrap(), b"v1");
+        assert_eq!(tx_version.get(b"k2").unwrap(), b"v2");
+    }
+
+    // Pins down the gap 'ingest_external_files's doc comment disclaims: ingestion doesn't go through
+    // 'GetForUpdate'-style locking, so it doesn't detect (and can't report) a collision with keys an
+    // open, uncommitted transaction is about to write to the same column family. Both the ingest and the
+    // later commit succeed with no error - the collision is resolved silently (whichever write ends up
+    // at the higher sequence number wins) rather than surfaced to either caller.
+    #[test]
+    fn storage_versioned_ingest_external_files_uncommitted_collision_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_ingest_external_files_uncommitted_collision_tests").unwrap();
+        let (_sst_tmp_dir, sst_dir) = test_dir("storage_versioned_ingest_external_files_uncommitted_collision_sst").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "from_transaction".as_ref())], &vec![]).unwrap();
+
+        let sst_path = join_path_strings(sst_dir.as_str(), "ingest.sst").unwrap();
+        let mut writer = rocksdb::SstFileWriter::create(&rocksdb::Options::default());
+        writer.open(sst_path.as_str()).unwrap();
+        writer.put(b"k1", b"from_ingest").unwrap();
+        writer.finish().unwrap();
+
+        // Ingesting into the same key the still-uncommitted 'tx' is about to write isn't rejected
+        assert!(storage.ingest_external_files("default", &[Path::new(sst_path.as_str())]).is_ok());
+
+        // Committing 'tx' afterwards isn't rejected either - no conflict is ever reported to either side
+        assert!(tx.commit("version_id1").is_ok());
+
+        // Whichever write ends up visible, it happened silently rather than via a detectable error
+        let final_value = storage.get(b"k1").unwrap();
+        assert!(final_value == b"from_transaction".as_bytes() || final_value == b"from_ingest".as_bytes());
+    }
+
+    #[test]
+    fn storage_versioned_snapshot_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_snapshot_tests").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        assert!(storage.create_snapshot("version_id1").is_err());
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        // A snapshot of a previous version reads the same content as a transaction opened against it,
+        // and is unaffected by further writes to the current state
+        let snapshot = storage.create_snapshot("version_id1").unwrap();
+        assert_eq!(snapshot.get(b"k1").unwrap(), b"v1");
+
+        let tx2 = storage.create_transaction(None).unwrap();
+        tx2.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx2.commit("version_id2").unwrap();
+
+        assert_eq!(snapshot.get(b"k1").unwrap(), b"v1");
+
+        // A second snapshot of the same version reuses the cached version DB and observes the same content
+        let snapshot_again = storage.create_snapshot("version_id1").unwrap();
+        assert_eq!(snapshot_again.get(b"k1").unwrap(), b"v1");
+    }
+
+    // Regression test for a 'VersionSnapshot' being handed out for a version whose cached 'TransactionDB'
+    // is then evicted from 'self.version_cache' by ordinary LRU pressure while the snapshot is still held -
+    // 'VersionSnapshot' must keep its own 'Arc' clone of that DB alive rather than dangling once the
+    // cache's own reference is dropped
+    #[test]
+    fn storage_versioned_snapshot_survives_cache_eviction_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_snapshot_survives_cache_eviction_tests").unwrap();
+
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        // Snapshotting it caches its checkpoint DB as entry 1/VERSION_CACHE_CAPACITY
+        let snapshot = storage.create_snapshot("version_id1").unwrap();
+        assert_eq!(snapshot.get(b"k1").unwrap(), b"v1");
+
+        // Committing and snapshotting enough further versions to push "version_id1" out of the cache
+        // purely through LRU pressure, without ever calling 'delete_version'/'trim_versions' on it
+        for i in 2..=(VERSION_CACHE_CAPACITY + 1) {
+            let version_id = format!("version_id{}", i);
+            let tx = storage.create_transaction(None).unwrap();
+            tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+            tx.commit(version_id.as_str()).unwrap();
+            storage.create_snapshot(version_id.as_str()).unwrap();
+        }
+
+        // "version_id1"'s cached DB has now been evicted, but the already-held 'snapshot' must still be
+        // backed by its own 'Arc' clone rather than a dangling borrow into the cache
+        assert_eq!(snapshot.get(b"k1").unwrap(), b"v1");
+    }
+
     #[test]
     fn storage_versioned_cf_tests(){
         let (_tmp_dir, storage_path) = test_dir("storage_versioned_cf_tests").unwrap();
@@ -743,4 +1411,112 @@ mod test {
         // testing the Reader interface of the storage
         test_reader(&storage);
     }
+
+    #[test]
+    fn storage_versioned_snapshot_transaction_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_snapshot_transaction_tests").unwrap();
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        // Committing an initial value for 'k1' as the first version
+        let tx0 = storage.create_transaction(None).unwrap();
+        tx0.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx0.commit("version_id1").unwrap();
+
+        let default_cf = storage.get_column_family("default").unwrap();
+
+        let snapshot_tx = storage.create_transaction_with_snapshot().unwrap();
+        // get_for_update reads the pinned snapshot's value and registers 'k1' for commit-time conflict validation
+        assert_eq!(snapshot_tx.get_for_update_cf(default_cf, b"k1", true).unwrap().unwrap(), b"v1");
+
+        // No concurrent writer touched 'k1' since the snapshot was taken, so the commit succeeds
+        snapshot_tx.update_cf(default_cf, &vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        assert!(snapshot_tx.commit("version_id2").is_ok());
+        assert_eq!(storage.get(b"k1").unwrap(), b"v2");
+
+        // A fast-fail transaction configured via 'create_transaction_with_options' should not block waiting
+        // to acquire a lock already held by another in-flight transaction
+        let tx1 = storage.create_transaction(None).unwrap();
+        tx1.update(&vec![("k2".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+
+        let tx2 = storage.create_transaction_with_options(&TransactionLockOptions{
+            lock_timeout_ms: 0,
+            ..TransactionLockOptions::default()
+        }).unwrap();
+        assert!(tx2.update(&vec![("k2".as_ref(), "v2".as_ref())], &vec![]).is_err());
+
+        tx1.commit("version_id3").unwrap();
+        assert_eq!(storage.get(b"k2").unwrap(), b"v1");
+    }
+
+    #[test]
+    fn storage_versioned_format_guard_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_format_guard_tests").unwrap();
+
+        // A freshly created storage records its format descriptor without it showing up as a version
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+        assert!(storage.list_versions().unwrap().is_empty());
+        drop(storage);
+
+        // Reopening with the same 'versions_stored' validates cleanly
+        assert!(StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).is_ok());
+
+        // Reopening with a different 'versions_stored' is rejected as an incompatible format
+        let result = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED + 1);
+        assert!(result.is_err());
+        assert!(format::is_incompatible_format_error(&result.err().unwrap()));
+    }
+
+    #[test]
+    fn storage_versioned_live_snapshot_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_live_snapshot_tests").unwrap();
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+        let default_cf = storage.get_column_family("default").unwrap();
+
+        let tx0 = storage.create_transaction(None).unwrap();
+        tx0.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx0.commit("version_id1").unwrap();
+
+        // Pinning a point-in-time view of CurrentState after 'k1' = 'v1' was committed - distinct from
+        // 'create_snapshot(version_id)', which views a specific past checkpoint version rather than the
+        // live DB as of now
+        let snapshot = storage.snapshot().unwrap();
+        assert_eq!(storage.get_cf_opt(default_cf, b"k1", Some(&snapshot)).unwrap(), b"v1");
+
+        // A version committed after the snapshot was taken isn't observed through the snapshot
+        let tx1 = storage.create_transaction(None).unwrap();
+        tx1.update(&vec![("k1".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+        tx1.commit("version_id2").unwrap();
+
+        assert_eq!(storage.get(b"k1").unwrap(), b"v2");
+        assert_eq!(storage.get_cf_opt(default_cf, b"k1", Some(&snapshot)).unwrap(), b"v1");
+
+        // Passing no snapshot reads the live state, same as the plain Reader methods
+        assert_eq!(storage.get_cf_opt(default_cf, b"k1", None).unwrap(), b"v2");
+    }
+
+    // Regression test for a 'version_id' containing a tab or newline silently corrupting the manifest's
+    // tab-separated, newline-terminated log format on replay (see 'version_store::validate_version_id') -
+    // 'create_version' (reached here via 'commit') must reject it up front instead
+    #[test]
+    fn storage_versioned_create_version_rejects_unescapable_version_id_tests(){
+        let (_tmp_dir, storage_path) = test_dir("storage_versioned_create_version_rejects_unescapable_version_id_tests").unwrap();
+        let storage = StorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        assert!(tx.commit("version\tid").is_err());
+
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        assert!(tx.commit("version\nid").is_err());
+
+        // Neither rejected attempt left a version behind
+        assert!(storage.last_version().unwrap().is_none());
+
+        // A version_id without a tab or newline is unaffected
+        let tx = storage.create_transaction(None).unwrap();
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        assert!(tx.commit("version_id1").is_ok());
+        assert_eq!(storage.last_version().unwrap().unwrap(), "version_id1");
+    }
 }
@@ -0,0 +1,211 @@
+use rocksdb::{OptimisticTransactionDB, Options, Error};
+use crate::common::storage::ColumnFamiliesManager;
+use crate::common::{InternalReader, Reader, InternalRef, join_path_strings};
+use crate::TransactionInternal;
+use rocksdb::TransactionDB;
+use rocksdb::transactions::ops::{OpenCF, OptimisticTransactionBegin, CreateCheckpointObject};
+use std::path::Path;
+use std::sync::Mutex;
+use itertools::Itertools;
+use crate::storage_versioned::manifest::{VersionManifest, RecoveredVersions};
+use crate::storage_versioned::retention::RetentionPolicy;
+use crate::storage_versioned::optimistic_transaction::OptimisticTransactionVersioned;
+use crate::storage_versioned::version_store;
+
+// Delimiter between version number and version ID in a version (i.e. checkpoint) directory name; kept
+// in sync with 'StorageVersioned::VERSION_DELIMITER' since both read/write the same kind of directory name
+const VERSION_DELIMITER: &str = "__";
+
+// Optimistic-transaction counterpart of 'StorageVersioned': CurrentState is backed by an
+// OptimisticTransactionDB (see 'crate::storage::optimistic::OptimisticStorage'), so write-write conflicts
+// between concurrently created transactions are only detected at
+// 'OptimisticTransactionVersioned::commit' time rather than by blocking on per-key locks.
+//
+// This reuses 'storage_versioned::manifest', 'storage_versioned::retention' and
+// 'storage_versioned::version_store' as-is, since none of them depend on which RocksDB transaction engine
+// CurrentState uses - every version is still a real checkpoint directory under 'Versions', tracked by the
+// same append-only manifest format and the same dedup/retention bookkeeping 'StorageVersioned' uses.
+//
+// Deliberately NOT carried over from 'StorageVersioned', as a first step rather than a full port:
+// - 'create_snapshot'/'VersionSnapshot' and the 'version_cache' LRU backing it (no read access to a
+//   previous version's point-in-time state here - only 'list_versions' and the checkpoint directories
+//   themselves; 'OptimisticStorageVersioned::open' doesn't open one, since it expects a 'CurrentState'
+//   subdirectory under the path it's given)
+// - 'open_with_migrations'/schema migrations and 'open_with_retention_policy' (always uses
+//   'RetentionPolicy::KeepLatest(versions_stored)')
+// - 'rollback'/'rollback_to'/'diff'/'diff_cf' and the on-disk format guard ('storage_versioned::format'
+//   is written against 'TransactionDB' specifically)
+// A later change can port these the same way this type ports 'create_version'/'trim_versions', once
+// there's a concrete need for them in optimistic mode.
+pub struct OptimisticStorageVersioned {
+    db: OptimisticTransactionDB, // handle of an opened DB which contains current state of a storage
+    versions_path: String,       // absolute path to the 'Versions' directory
+    versions_stored: usize,      // number of the latest versions of storage to be stored; 0 disables versioning entirely
+    retention_policy: RetentionPolicy, // always 'RetentionPolicy::KeepLatest(versions_stored)' for now
+    manifest: Mutex<VersionManifest>, // append-only log of the version set's changes, used to recover it on 'open'
+    version_set: Mutex<RecoveredVersions>, // authoritative in-memory cache of the version set, rebuilt from 'manifest' on 'open'
+}
+
+impl InternalRef for OptimisticStorageVersioned {
+    fn db_ref(&self) -> Option<&TransactionDB> { None }
+    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { None }
+
+    fn transaction_ref(&self) -> Option<&TransactionInternal> { None }
+    fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal> { None }
+
+    fn optimistic_db_ref(&self) -> Option<&OptimisticTransactionDB> { Some(&self.db) }
+    fn optimistic_db_ref_mut(&mut self) -> Option<&mut OptimisticTransactionDB> { Some(&mut self.db) }
+}
+
+impl InternalReader for OptimisticStorageVersioned {}
+impl Reader for OptimisticStorageVersioned {}
+impl ColumnFamiliesManager for OptimisticStorageVersioned {}
+
+impl OptimisticStorageVersioned {
+    // Opens an optimistic-mode versioned storage located by a specified path or creates a new one if the
+    // directory by a specified path doesn't exist and 'create_if_missing' is true.
+    // The 'versions_stored' parameter specifies how many latest versions (0 or more) should be stored.
+    // Returns Result with OptimisticStorageVersioned instance or Err with a describing message if some error occurred
+    pub fn open(path: &str, create_if_missing: bool, versions_stored: usize) -> Result<Self, Error> {
+        let paths = version_store::open_paths(path, create_if_missing)?;
+
+        let mut opts = Options::default();
+        opts.create_if_missing(create_if_missing);
+
+        let db =
+            if paths.db_path_existed {
+                OptimisticTransactionDB::open_cf_all(&opts, &paths.db_path)?
+            } else {
+                OptimisticTransactionDB::open_cf_default(&opts, &paths.db_path)?
+            };
+
+        let base_path = version_store::absolute_path(path)?;
+        let versions_path = version_store::absolute_path(paths.versions_path.as_str())?;
+
+        let mut manifest = VersionManifest::open(base_path.as_str())?;
+        let version_set = version_store::recover(&mut manifest, base_path.as_str(), versions_path.as_str())?;
+
+        let storage = Self{
+            db,
+            versions_path,
+            versions_stored,
+            retention_policy: RetentionPolicy::KeepLatest(versions_stored),
+            manifest: Mutex::new(manifest),
+            version_set: Mutex::new(version_set),
+        };
+        storage.trim_versions()?;
+
+        Ok(storage)
+    }
+
+    // Removes whichever versions 'self.retention_policy' no longer wants kept, appending a
+    // 'DeleteVersion' edit to the manifest for each one removed; see 'version_store::delete_version' for
+    // why this doesn't necessarily clear a directory for every version removed (aliases may share one).
+    // Unlike 'StorageVersioned::trim_versions', there's no 'version_cache' to evict an owner from here.
+    fn trim_versions(&self) -> Result<(), Error> {
+        let mut version_set = self.version_set.lock().unwrap();
+        let mut manifest = self.manifest.lock().unwrap();
+        version_store::trim_versions(&self.retention_policy, &mut version_set, &mut manifest, self.versions_path.as_str(), |_| {})
+    }
+
+    // Creates a new storage's version (checkpoint of the CurrentState) in the 'Versions' directory, deduping
+    // against an existing checkpoint with the same content digest exactly as 'StorageVersioned::create_version'
+    // does, then trims whichever versions 'self.retention_policy' no longer wants kept.
+    // Called from 'OptimisticTransactionVersioned::commit' as a side effect of a successful commit.
+    // Returns Result with error message if a version with specified ID already exists or some other error occurred
+    pub(crate) fn create_version(&self, version_id: &str) -> Result<(), Error> {
+        if self.versions_stored == 0 {
+            return Ok(())
+        }
+
+        // Rejecting a 'version_id' the manifest's log format couldn't round-trip before paying for a
+        // checkpoint that would just be discarded - see 'version_store::validate_version_id'
+        version_store::validate_version_id(version_id)?;
+
+        // Checking up front (before paying for a checkpoint that would just be discarded) whether the
+        // specified 'version_id' already exists; 'version_store::finalize_version' re-checks this itself
+        // right before applying the edit, as the actual source of truth
+        if self.version_set.lock().unwrap().versions.get(version_id).is_some() {
+            return Err(Error::new("Specified version already exists".into()))
+        }
+
+        let scratch_path_str = join_path_strings(
+            self.versions_path.as_str(),
+            (".tmp".to_owned() + VERSION_DELIMITER + version_id).as_str()
+        )?;
+        let scratch_path = Path::new(&scratch_path_str);
+
+        self.db.create_checkpoint_object()?.create_checkpoint(scratch_path)?;
+        let hash = version_store::compute_checkpoint_hash(scratch_path, self.db.latest_sequence_number())?;
+
+        let mut version_set = self.version_set.lock().unwrap();
+        let mut manifest = self.manifest.lock().unwrap();
+        version_store::finalize_version(&mut version_set, &mut manifest, self.versions_path.as_str(), version_id, scratch_path_str.as_str(), hash)?;
+        drop(version_set);
+        drop(manifest);
+
+        self.trim_versions()
+    }
+
+    // Returns a sorted by creation order list of all existing version IDs
+    pub fn list_versions(&self) -> Result<Vec<String>, Error> {
+        Ok(version_store::sorted_version_ids(self.version_set.lock().unwrap().versions.clone()))
+    }
+
+    // Creates and returns an OptimisticTransactionVersioned over the CurrentState DB
+    // Returns Err with describing message if some error occurred
+    pub fn create_transaction(&self) -> Result<OptimisticTransactionVersioned, Error> {
+        Ok(OptimisticTransactionVersioned::new(self.db.transaction_default()?, self))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::storage_versioned::optimistic::OptimisticStorageVersioned;
+    use crate::storage_versioned::optimistic_transaction::OptimisticTransactionVersioned;
+    use crate::common::transaction::TransactionBasic;
+    use crate::common::{Reader, test_dir};
+
+    const VERSIONS_STORED: usize = 10;
+
+    #[test]
+    fn optimistic_storage_versioned_tests(){
+        let (_tmp_dir, storage_path) = test_dir("optimistic_storage_versioned_tests").unwrap();
+
+        assert!(OptimisticStorageVersioned::open(storage_path.as_str(), false, VERSIONS_STORED).is_err());
+
+        drop(OptimisticStorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap());
+
+        let storage = OptimisticStorageVersioned::open(storage_path.as_str(), false, VERSIONS_STORED).unwrap();
+        let tx = storage.create_transaction().unwrap();
+
+        assert!(tx.is_empty());
+        tx.update(&vec![("k1".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx.commit("version_id1").unwrap();
+
+        assert_eq!(storage.get(b"k1").unwrap(), b"v1");
+        assert_eq!(storage.list_versions().unwrap(), vec!["version_id1".to_owned()]);
+    }
+
+    #[test]
+    fn optimistic_storage_versioned_conflict_tests(){
+        let (_tmp_dir, storage_path) = test_dir("optimistic_storage_versioned_conflict_tests").unwrap();
+        let storage = OptimisticStorageVersioned::open(storage_path.as_str(), true, VERSIONS_STORED).unwrap();
+
+        let tx1 = storage.create_transaction().unwrap();
+        let tx2 = storage.create_transaction().unwrap();
+
+        tx1.update(&vec![("k".as_ref(), "v1".as_ref())], &vec![]).unwrap();
+        tx2.update(&vec![("k".as_ref(), "v2".as_ref())], &vec![]).unwrap();
+
+        // The first committer wins, and its version is created...
+        assert!(tx1.commit("version_id1").is_ok());
+        assert_eq!(storage.list_versions().unwrap(), vec!["version_id1".to_owned()]);
+
+        // ...and the second commit fails with a write-write conflict, not a silent overwrite, and
+        // creates no version of its own
+        let err = tx2.commit("version_id2").unwrap_err();
+        assert!(OptimisticTransactionVersioned::is_conflict(&err));
+        assert_eq!(storage.list_versions().unwrap(), vec!["version_id1".to_owned()]);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::OptimisticTransactionInternal;
+use rocksdb::{Error, TransactionDB};
+use crate::TransactionInternal;
+use crate::common::{InternalReader, Reader, InternalRef};
+use crate::common::transaction::TransactionBasic;
+use crate::common::transaction::is_conflict_error;
+use crate::storage_versioned::optimistic::OptimisticStorageVersioned;
+
+// Optimistic-transaction counterpart of 'TransactionVersioned': always started against
+// 'OptimisticStorageVersioned's CurrentState, since 'OptimisticStorageVersioned' doesn't support opening a
+// transaction against a previous version (see the module-level doc comment on 'OptimisticStorageVersioned').
+pub struct OptimisticTransactionVersioned<'a> {
+    transaction: OptimisticTransactionInternal,
+    // &OptimisticStorageVersioned - needed to create a new version of CurrentState after a successful commit
+    storage: &'a OptimisticStorageVersioned,
+}
+
+impl InternalRef for OptimisticTransactionVersioned<'_> {
+    fn db_ref(&self) -> Option<&TransactionDB> { None }
+    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { None }
+
+    fn transaction_ref(&self) -> Option<&TransactionInternal> { None }
+    fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal> { None }
+
+    fn optimistic_transaction_ref(&self) -> Option<&OptimisticTransactionInternal> { Some(&self.transaction) }
+    fn optimistic_transaction_ref_mut(&mut self) -> Option<&mut OptimisticTransactionInternal> { Some(&mut self.transaction) }
+}
+
+impl InternalReader for OptimisticTransactionVersioned<'_> {}
+impl Reader for OptimisticTransactionVersioned<'_> {}
+impl TransactionBasic for OptimisticTransactionVersioned<'_> {}
+
+impl<'a> OptimisticTransactionVersioned<'a> {
+    // Creates a new instance of OptimisticTransactionVersioned (a wrapper for OptimisticTransactionInternal)
+    // started against 'storage's CurrentState
+    pub(crate) fn new(transaction: OptimisticTransactionInternal, storage: &'a OptimisticStorageVersioned) -> Self {
+        Self{ transaction, storage }
+    }
+
+    // Commits all of this transaction's updates into the related OptimisticStorageVersioned and, if that
+    // succeeds, creates a new version (checkpoint) identified by 'version_id'. A write-write conflict with
+    // a concurrently committed transaction fails the commit (see 'is_conflict') before any version is created.
+    // Returns Result with an error message if some error occurred
+    pub fn commit(&self, version_id: &str) -> Result<(), Error> {
+        self.transaction.commit()?;
+        self.storage.create_version(version_id)
+    }
+
+    // Returns true if 'error' was raised by 'commit' because of a write-write conflict with another
+    // transaction, as opposed to some other I/O or DB failure
+    pub fn is_conflict(error: &Error) -> bool {
+        is_conflict_error(error)
+    }
+}
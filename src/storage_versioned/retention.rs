@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+// How many, and which, historical versions 'StorageVersioned::trim_versions' keeps once a new version
+// is committed. 'KeepLatest' is the original fixed sliding window (a contiguous run of the 'n' most
+// recent version numbers); 'Tiered' and 'TimeBased' deliberately keep a non-contiguous subset of
+// version numbers, trading an unbroken recent history for coarser-grained long-tail retention at a
+// fraction of the disk cost. Selected once, at 'StorageVersioned::open_with_retention_policy' time.
+pub enum RetentionPolicy {
+    // Keep only the 'n' most recent versions by version number; everything older is removed. The
+    // default policy used by 'StorageVersioned::open', equivalent to the original hard-coded behavior.
+    KeepLatest(usize),
+    // Keep every version within the most recent 'recent' version numbers, plus every 'interval'-th
+    // version number older than that (an 'interval' of 0 is treated as 1, i.e. no extra thinning).
+    Tiered{ recent: usize, interval: usize },
+    // Keep every version whose recorded creation timestamp is less than 'max_age' old, regardless of
+    // how many versions that is.
+    TimeBased(Duration),
+}
+
+impl RetentionPolicy {
+    // Returns the VersionIDs which fall outside this policy's retention window and should be removed
+    // by 'StorageVersioned::trim_versions'. 'versions' is the full VersionID -> VersionNumber map;
+    // 'created_at' the VersionID -> creation unix timestamp (seconds) map recorded for each one when
+    // it was committed (see 'VersionEdit::AddVersion'/'AddAlias'). 'now' is the current unix timestamp
+    // (seconds), passed in rather than read here so this stays a pure, independently testable function.
+    pub(crate) fn ids_to_remove(&self, versions: &HashMap<String, usize>, created_at: &HashMap<String, u64>, now: u64) -> Vec<String> {
+        match self {
+            RetentionPolicy::KeepLatest(n) => {
+                let max_version_number = match versions.values().copied().max() {
+                    Some(max) => max,
+                    None => return Vec::new(),
+                };
+                if versions.len() <= *n {
+                    return Vec::new()
+                }
+                assert!(max_version_number >= *n);
+
+                let min_version_number = max_version_number - n + 1;
+                versions.iter()
+                    .filter(|&(_, &num)| num < min_version_number)
+                    .map(|(id, _)| id.clone())
+                    .collect()
+            }
+            RetentionPolicy::Tiered{ recent, interval } => {
+                let max_version_number = match versions.values().copied().max() {
+                    Some(max) => max,
+                    None => return Vec::new(),
+                };
+                let recent_floor = max_version_number.saturating_sub(recent.saturating_sub(1));
+                let interval = (*interval).max(1);
+
+                versions.iter()
+                    .filter(|&(_, &num)| num < recent_floor && num % interval != 0)
+                    .map(|(id, _)| id.clone())
+                    .collect()
+            }
+            RetentionPolicy::TimeBased(max_age) => {
+                let cutoff = now.saturating_sub(max_age.as_secs());
+                versions.keys()
+                    .filter(|id| created_at.get(id.as_str()).copied().unwrap_or(0) < cutoff)
+                    .cloned()
+                    .collect()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn versions(numbers: &[(&str, usize)]) -> HashMap<String, usize> {
+        numbers.iter().map(|&(id, num)| (id.to_owned(), num)).collect()
+    }
+
+    #[test]
+    fn retention_policy_keep_latest_tests(){
+        let versions = versions(&[("v0", 0), ("v1", 1), ("v2", 2), ("v3", 3)]);
+
+        // Nothing to remove while the window isn't exceeded
+        assert!(RetentionPolicy::KeepLatest(4).ids_to_remove(&versions, &HashMap::new(), 0).is_empty());
+
+        let mut removed = RetentionPolicy::KeepLatest(2).ids_to_remove(&versions, &HashMap::new(), 0);
+        removed.sort();
+        assert_eq!(removed, vec!["v0".to_owned(), "v1".to_owned()]);
+    }
+
+    #[test]
+    fn retention_policy_tiered_tests(){
+        // Versions 0..=9; keep the most recent 3 contiguously, plus every 2nd one below that
+        let versions: HashMap<String, usize> = (0usize ..= 9)
+            .map(|num| (num.to_string(), num))
+            .collect();
+
+        let mut removed = RetentionPolicy::Tiered{ recent: 3, interval: 2 }.ids_to_remove(&versions, &HashMap::new(), 0);
+        removed.sort();
+        // Recent floor is 9 - (3 - 1) = 7, so 7, 8, 9 are always kept; below that, only odd-numbered
+        // versions (not a multiple of the interval) are removed: 1, 3, 5
+        assert_eq!(removed, vec!["1".to_owned(), "3".to_owned(), "5".to_owned()]);
+    }
+
+    #[test]
+    fn retention_policy_time_based_tests(){
+        let versions = versions(&[("old", 0), ("recent", 1)]);
+        let created_at: HashMap<String, u64> = [("old".to_owned(), 100), ("recent".to_owned(), 190)].into_iter().collect();
+
+        // At 'now' == 200 with a max age of 50s, "old" (created at 100, age 100s) falls outside the
+        // window while "recent" (created at 190, age 10s) doesn't
+        let removed = RetentionPolicy::TimeBased(Duration::from_secs(50)).ids_to_remove(&versions, &created_at, 200);
+        assert_eq!(removed, vec!["old".to_owned()]);
+    }
+}
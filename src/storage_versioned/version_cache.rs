@@ -0,0 +1,84 @@
+use rocksdb::{TransactionDB, Error};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+// Bounded LRU cache of opened version (checkpoint) DBs, keyed by VersionID. Backs
+// 'StorageVersioned::create_snapshot' so that repeated snapshot reads of the same version don't repay
+// the cost of reopening its TransactionDB. Entries are reference-counted (rather than merely boxed) so
+// that a 'VersionSnapshot' can keep its own 'Arc' clone of the TransactionDB it was created from: LRU
+// eviction (or an explicit 'evict') then only drops the cache's reference, and the DB itself stays open
+// for as long as any outstanding 'VersionSnapshot' still holds a clone - see 'StorageVersioned::create_snapshot'.
+pub(crate) struct VersionCache {
+    capacity: usize,
+    dbs: HashMap<String, Arc<TransactionDB>>,
+    order: VecDeque<String>, // least-recently-used at the front, most-recently-used at the back
+}
+
+impl VersionCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        VersionCache{ capacity: capacity.max(1), dbs: HashMap::new(), order: VecDeque::new() }
+    }
+
+    // Returns the cached version DB for 'version_id' (as a cloned 'Arc', so the caller can keep it alive
+    // past this cache evicting its own reference), marking it most-recently-used, opening and caching a
+    // new one via 'open_fn' if absent. If the cache is already at capacity, the least-recently-used entry
+    // is evicted first (its TransactionDB only actually closes once every outstanding 'Arc' clone of it,
+    // e.g. one held by a 'VersionSnapshot', is also dropped).
+    pub(crate) fn get_or_open(&mut self, version_id: &str, open_fn: impl FnOnce() -> Result<TransactionDB, Error>) -> Result<Arc<TransactionDB>, Error> {
+        if self.dbs.contains_key(version_id) {
+            self.order.retain(|id| id != version_id);
+        } else {
+            if self.dbs.len() >= self.capacity {
+                if let Some(evicted_id) = self.order.pop_front() {
+                    self.dbs.remove(&evicted_id);
+                }
+            }
+            self.dbs.insert(version_id.to_owned(), Arc::new(open_fn()?));
+        }
+        self.order.push_back(version_id.to_owned());
+        Ok(self.dbs.get(version_id).unwrap().clone())
+    }
+
+    // Drops the cached DB for 'version_id', if any, e.g. when its version is removed by 'trim_versions'/'rollback'
+    pub(crate) fn evict(&mut self, version_id: &str) {
+        self.dbs.remove(version_id);
+        self.order.retain(|id| id != version_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::common::{test_dir, join_path_strings};
+    use rocksdb::Options;
+    use rocksdb::transactions::ops::OpenCF;
+
+    fn open_version_db(base_path: &str, id: &str) -> TransactionDB {
+        let path = join_path_strings(base_path, id).unwrap();
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        TransactionDB::open_cf_default(&opts, path.as_str()).unwrap()
+    }
+
+    #[test]
+    fn version_cache_lru_eviction_tests(){
+        let (_tmp_dir, base_path) = test_dir("version_cache_lru_eviction_tests").unwrap();
+        let mut cache = VersionCache::new(2);
+
+        cache.get_or_open("v1", || Ok(open_version_db(base_path.as_str(), "v1"))).unwrap();
+        cache.get_or_open("v2", || Ok(open_version_db(base_path.as_str(), "v2"))).unwrap();
+        assert!(cache.dbs.contains_key("v1"));
+        assert!(cache.dbs.contains_key("v2"));
+
+        // Touching 'v1' makes it most-recently-used, so 'v2' is evicted to make room for 'v3'
+        cache.get_or_open("v1", || panic!("v1 should already be cached")).unwrap();
+        cache.get_or_open("v3", || Ok(open_version_db(base_path.as_str(), "v3"))).unwrap();
+
+        assert!(cache.dbs.contains_key("v1"));
+        assert!(!cache.dbs.contains_key("v2"));
+        assert!(cache.dbs.contains_key("v3"));
+
+        cache.evict("v1");
+        assert!(!cache.dbs.contains_key("v1"));
+    }
+}
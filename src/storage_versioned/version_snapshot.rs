@@ -0,0 +1,29 @@
+use std::sync::Arc;
+use rocksdb::{TransactionDB, Snapshot};
+use crate::common::{InternalRef, InternalReader, Reader};
+use crate::TransactionInternal;
+
+// A read-only, point-in-time view over a previous version of a StorageVersioned, returned by
+// 'StorageVersioned::create_snapshot'. Implements Reader/InternalReader for point lookups, multi-get,
+// and prefix iteration like any other Reader, but deliberately doesn't implement TransactionBasic -
+// there is no path to write through it.
+pub struct VersionSnapshot<'a> {
+    pub(crate) snapshot: Snapshot<'a>,
+    // Keeps the snapshotted version's 'TransactionDB' open for as long as this 'VersionSnapshot' is
+    // retained, independent of 'VersionCache' evicting its own reference - see 'StorageVersioned::create_snapshot'.
+    // Never read directly, only held for its 'Drop' ordering relative to 'snapshot' above.
+    pub(crate) _db: Arc<TransactionDB>,
+}
+
+impl<'a> InternalRef for VersionSnapshot<'a> {
+    fn db_ref(&self) -> Option<&TransactionDB> { None }
+    fn db_ref_mut(&mut self) -> Option<&mut TransactionDB> { None }
+
+    fn transaction_ref(&self) -> Option<&TransactionInternal> { None }
+    fn transaction_ref_mut(&mut self) -> Option<&mut TransactionInternal> { None }
+
+    fn snapshot_ref(&self) -> Option<&Snapshot> { Some(&self.snapshot) }
+}
+
+impl<'a> InternalReader for VersionSnapshot<'a> {}
+impl<'a> Reader for VersionSnapshot<'a> {}
@@ -0,0 +1,268 @@
+use rocksdb::Error;
+use std::path::Path;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::fs::rename;
+use itertools::Itertools;
+use crate::common::{clear_path, join_path_strings};
+use crate::storage_versioned::manifest::{VersionManifest, VersionEdit, RecoveredVersions};
+use crate::storage_versioned::retention::RetentionPolicy;
+use std::collections::HashMap;
+
+// Version-bookkeeping shared by 'StorageVersioned' and 'OptimisticStorageVersioned': both keep every
+// version as a checkpoint directory under a 'Versions' directory next to a 'CurrentState' DB directory,
+// tracked by the same append-only manifest format, deduped against identical content and retained the
+// same way, regardless of which RocksDB transaction engine backs 'CurrentState' itself. This module is
+// that shared logic, factored out so a fix to it (the content-hash dedup, a new 'RetentionPolicy'
+// variant, the manifest format) only needs making once instead of drifting between two near-identical
+// copies. What's deliberately NOT here: anything that has to go through the DB handle itself (opening
+// it, creating its checkpoint object, reading its latest sequence number) - those differ by engine type
+// and stay the caller's responsibility, passed in as plain values ('latest_sequence_number') or done by
+// the caller before calling in ('create_checkpoint').
+
+// Directory for storing a current state of a storage (DB)
+pub(crate) const DB_DIR: &str = "CurrentState";
+// Directory for storing versions of the storage (Checkpoints)
+pub(crate) const VERSIONS_DIR: &str = "Versions";
+// Delimiter between version number and version ID in a version (i.e. checkpoint) directory name
+const VERSION_DELIMITER: &str = "__";
+
+// The two on-disk paths an 'open' needs before it can open its own DB handle
+pub(crate) struct OpenPaths {
+    pub(crate) db_path: String,
+    pub(crate) db_path_existed: bool,
+    pub(crate) versions_path: String,
+}
+
+// Creates (if 'create_if_missing') or validates the 'CurrentState' and 'Versions' directories under
+// 'path', in that order, so neither directory is left behind if the other fails to be created - unlike
+// creating the DB directory and only then preparing 'Versions', this doesn't need the caller's DB handle
+// opened (and possibly dropped again on failure) to know whether 'db_path' existed already.
+// Returns Result with Error if either directory doesn't exist and can't be created
+pub(crate) fn open_paths(path: &str, create_if_missing: bool) -> Result<OpenPaths, Error> {
+    let db_path = join_path_strings(path, DB_DIR)?;
+    let db_path_existed = Path::new(db_path.as_str()).exists();
+    if !db_path_existed {
+        if !create_if_missing {
+            return Err(Error::new("No need to create a DB (DB does not exist and the create_if_missing == false)".into()));
+        } else if std::fs::create_dir_all(&db_path).is_err() {
+            return Err(Error::new("DB directory can't be created".into()));
+        }
+    }
+
+    let versions_path = join_path_strings(path, VERSIONS_DIR)?;
+    if !Path::new(versions_path.as_str()).exists() && std::fs::create_dir(&versions_path).is_err() {
+        return if std::fs::remove_dir(&db_path).is_ok() {
+            Err(Error::new("Versions directory can't be created".into()))
+        } else {
+            Err(Error::new("Versions directory can't be created; Can't clean the DB directory".into()))
+        }
+    }
+
+    Ok(OpenPaths{ db_path, db_path_existed, versions_path })
+}
+
+// Converts path into absolute format with Path::canonicalize method
+pub(crate) fn absolute_path(path: &str) -> Result<String, Error> {
+    if let Ok(path_buf) = Path::new(path).canonicalize(){
+        if let Some(path_str) = path_buf.to_str() {
+            Ok(String::from(path_str))
+        } else {
+            Err(Error::new("Can't convert the canonicalized path into string".into()))
+        }
+    } else {
+        Err(Error::new("Path can't be canonicalized".into()))
+    }
+}
+
+// Rebuilds the version set by replaying 'base_path's manifest (an append-only log, so replay has no
+// ordering/contiguity requirement on version numbers), then reconciles it against the checkpoint
+// directories actually present under 'versions_path': any owner whose checkpoint directory is missing
+// (e.g. a crash between appending its AddVersion edit and finishing the checkpoint write) is dropped,
+// along with every version still aliased onto it, each with a compensating DeleteVersion edit appended
+// so a later replay doesn't resurrect it.
+pub(crate) fn recover(manifest: &mut VersionManifest, base_path: &str, versions_path: &str) -> Result<RecoveredVersions, Error> {
+    let mut version_set = VersionManifest::replay(base_path)?;
+
+    let missing_owners: Vec<String> = version_set.owner_numbers.iter()
+        .filter(|&(owner_id, &number)| {
+            compose_version_path_str(versions_path, owner_id, number)
+                .map(|path| !Path::new(path.as_str()).exists())
+                .unwrap_or(true)
+        })
+        .map(|(owner_id, _)| owner_id.clone())
+        .collect();
+
+    for owner_id in missing_owners {
+        let dependent_ids: Vec<String> = version_set.versions.keys()
+            .filter(|id| version_set.owner_of(id) == owner_id)
+            .cloned().collect();
+
+        for id in dependent_ids {
+            let edit = VersionEdit::DeleteVersion{ id };
+            manifest.append(&edit)?;
+            version_set.apply(&edit);
+        }
+    }
+
+    Ok(version_set)
+}
+
+// Composes directory name for a specified version ID and its number as 'versionNumber__versionID'
+pub(crate) fn compose_version_dir_name(version_id: &str, version_number: usize) -> String {
+    version_number.to_string() + VERSION_DELIMITER + version_id
+}
+
+// Composes absolute path for a specified version as: versions_path + '/' + version_dir_name
+pub(crate) fn compose_version_path_str(versions_path: &str, version_id: &str, version_number: usize) -> Result<String, Error> {
+    join_path_strings(versions_path, compose_version_dir_name(version_id, version_number).as_str())
+}
+
+// Returns the next number for a given list of versions' numbers or 0 if the list is empty
+fn next_version_number(all_versions_numbers: &[usize]) -> usize {
+    all_versions_numbers.iter().max().map_or(0, |max| max + 1)
+}
+
+// Computes a stable content digest for the checkpoint directory at 'version_path', over the names and
+// sizes of every file in it plus the owning DB's latest sequence number (passed in by the caller, since reading it
+// is a DB-engine-specific call). Two checkpoints of the same unchanged CurrentState produce the same
+// digest, which 'finalize_version' uses to detect a no-op transition.
+pub(crate) fn compute_checkpoint_hash(version_path: &Path, latest_sequence_number: u64) -> Result<String, Error> {
+    let mut entries: Vec<(String, u64)> = std::fs::read_dir(version_path)
+        .map_err(|e| Error::new(format!("Can't read checkpoint directory for hashing: {:?}", e)))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.metadata().ok()?.len())))
+        .collect();
+    entries.sort();
+
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    latest_sequence_number.hash(&mut hasher);
+    Ok(format!("{:x}", hasher.finish()))
+}
+
+// Deletes 'id' from the version set (manifest + in-memory), physically removing its checkpoint
+// directory only once the last version referencing it (itself or an alias sharing its content) has been
+// removed; see 'RecoveredVersions::apply' for the refcounting this relies on. 'on_directory_removed' is
+// called right before the directory is cleared, so a caller keeping its own cache of opened version DBs
+// (e.g. 'StorageVersioned::version_cache') can evict the owner first.
+pub(crate) fn delete_version(
+    version_set: &mut RecoveredVersions,
+    manifest: &mut VersionManifest,
+    versions_path: &str,
+    id: &str,
+    mut on_directory_removed: impl FnMut(&str),
+) -> Result<(), Error> {
+    let owner_id = version_set.owner_of(id);
+    let owner_number = *version_set.owner_numbers.get(owner_id.as_str())
+        .ok_or_else(|| Error::new("Missing checkpoint directory metadata for version".into()))?;
+
+    let edit = VersionEdit::DeleteVersion{ id: id.to_owned() };
+    manifest.append(&edit)?;
+    version_set.apply(&edit);
+
+    if !version_set.directory_refs.contains_key(owner_id.as_str()) {
+        on_directory_removed(owner_id.as_str());
+        clear_path(compose_version_path_str(versions_path, owner_id.as_str(), owner_number)?.as_str())?;
+    }
+    Ok(())
+}
+
+// Removes whichever versions 'retention_policy' no longer wants kept, appending a 'DeleteVersion' edit
+// to the manifest for each one removed; see 'delete_version' for why this doesn't necessarily clear a
+// directory for every version removed (aliases may share one).
+pub(crate) fn trim_versions(
+    retention_policy: &RetentionPolicy,
+    version_set: &mut RecoveredVersions,
+    manifest: &mut VersionManifest,
+    versions_path: &str,
+    mut on_directory_removed: impl FnMut(&str),
+) -> Result<(), Error> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::new(format!("System clock error: {:?}", e)))?
+        .as_secs();
+    let ids_to_remove = retention_policy.ids_to_remove(&version_set.versions, &version_set.created_at, now);
+
+    for id in ids_to_remove {
+        delete_version(version_set, manifest, versions_path, id.as_str(), &mut on_directory_removed)?;
+    }
+    Ok(())
+}
+
+// Rejects a 'version_id' that couldn't round-trip through the manifest's tab-separated, newline-terminated
+// log format (see 'VersionEdit::serialize'/'deserialize'): an embedded tab would shift a later edit's field
+// boundaries on replay (for 'AddAlias', whose 'target_id' - itself a 'version_id' - isn't the line's last
+// field), and an embedded newline would split one edit across two physical lines, silently dropping the
+// second as malformed. Callers are expected to validate 'version_id' before doing any of the (comparatively
+// expensive) checkpoint work 'finalize_version' assumes is already done.
+// Returns Result with Error if 'version_id' contains a tab or newline character
+pub(crate) fn validate_version_id(version_id: &str) -> Result<(), Error> {
+    if version_id.contains('\t') || version_id.contains('\n') {
+        return Err(Error::new("Version ID can't contain a tab or newline character".into()))
+    }
+    Ok(())
+}
+
+// Everything a caller needs to finish creating a new version once it has already written a checkpoint of
+// CurrentState to the scratch directory at 'scratch_path_str' and computed its content 'hash': picks the
+// next version number, dedupes against 'content_index' (aliasing onto an existing checkpoint with
+// identical content instead of keeping a redundant copy), finalizes the checkpoint directory (rename, or
+// removal if it's a dupe) and appends/applies the resulting manifest edit. The checkpoint/alias exists on
+// disk (or is a no-op over an existing one) from the point this is called on; only after the edit is
+// durable is the new version considered committed.
+// Returns Result with Error if 'version_id' already exists or some other error occurred
+pub(crate) fn finalize_version(
+    version_set: &mut RecoveredVersions,
+    manifest: &mut VersionManifest,
+    versions_path: &str,
+    version_id: &str,
+    scratch_path_str: &str,
+    hash: String,
+) -> Result<(), Error> {
+    if version_set.versions.get(version_id).is_some() {
+        return Err(Error::new("Specified version already exists".into()))
+    }
+    let next_version_number = next_version_number(version_set.versions.values().copied().collect::<Vec<usize>>().as_slice());
+
+    let created_at = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::new(format!("System clock error: {:?}", e)))?
+        .as_secs();
+
+    let edit = if let Some(owner_id) = version_set.content_index.get(hash.as_str()).cloned() {
+        // Identical state already checkpointed under 'owner_id' - alias this version onto it instead of
+        // keeping a second, redundant checkpoint directory
+        clear_path(scratch_path_str)?;
+        VersionEdit::AddAlias{ id: version_id.to_owned(), number: next_version_number, target_id: owner_id, hash, created_at }
+    } else {
+        let version_path_str = compose_version_path_str(versions_path, version_id, next_version_number)?;
+        rename(scratch_path_str, version_path_str.as_str())
+            .map_err(|e| Error::new(format!("Can't finalize the checkpoint directory: {:?}", e)))?;
+        VersionEdit::AddVersion{ id: version_id.to_owned(), number: next_version_number, hash, created_at }
+    };
+
+    manifest.append(&edit)?;
+    version_set.apply(&edit);
+    Ok(())
+}
+
+// Composes the absolute path of a specific, already-created version's checkpoint directory, resolving
+// 'version_id' through 'version_set''s alias-owner mapping first (its own directory, or - if 'version_id'
+// is an alias - the directory of the version it aliases)
+pub(crate) fn version_path(version_set: &RecoveredVersions, versions_path: &str, version_id: &str) -> Result<String, Error> {
+    if version_set.versions.contains_key(version_id) {
+        let owner_id = version_set.owner_of(version_id);
+        let owner_number = *version_set.owner_numbers.get(owner_id.as_str())
+            .ok_or_else(|| Error::new("Missing checkpoint directory metadata for version".into()))?;
+        compose_version_path_str(versions_path, owner_id.as_str(), owner_number)
+    } else {
+        Err(Error::new("Specified version doesn't exist".into()))
+    }
+}
+
+// Returns every version ID in 'versions' ordered by version number (commit order), oldest first
+pub(crate) fn sorted_version_ids(versions: HashMap<String, usize>) -> Vec<String> {
+    versions.into_iter()
+        .sorted_by(|v1, v2| Ord::cmp(&v1.1, &v2.1))
+        .map(|(id, _)| id).collect()
+}